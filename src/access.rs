@@ -0,0 +1,365 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::clock::Clock;
+use crate::datetime::lenient;
+use crate::types::*;
+use crate::{Error, ErrorKind, Result};
+use json_api_client::types::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AccessState {
+    Active,
+    /// API access ends at `until`. Both are None if the revocation is only known from a webhook.
+    RevocationScheduled {
+        #[serde(with = "lenient::option")]
+        until: Option<DateTime>,
+        by: Option<RevokeType>,
+    },
+    Revoked {
+        #[serde(with = "lenient::option")]
+        at: Option<DateTime>,
+        by: Option<RevokeType>,
+    },
+}
+
+impl AccessState {
+    /// State from the `access_expires_at` and `access_revoked_by` fields of a response
+    pub fn from_fields(expires_at: Option<DateTime>, revoked_by: Option<&RevokeType>, now: DateTime) -> AccessState {
+        let by = revoked_by.cloned();
+        match expires_at {
+            Some(expires_at) if expires_at <= now => AccessState::Revoked { at: Some(expires_at), by },
+            Some(expires_at) => AccessState::RevocationScheduled { until: Some(expires_at), by },
+            None if by.is_some() => AccessState::Revoked { at: None, by },
+            None => AccessState::Active,
+        }
+    }
+
+    /// True if a newly reported `self` should replace the `known` state. States never weaken: a scheduled revocation
+    /// isn't replaced by an active state, nor a past revocation by a scheduled or active one.
+    pub(crate) fn replaces(&self, known: &AccessState) -> bool {
+        self.strength() >= known.strength()
+    }
+
+    fn strength(&self) -> u8 {
+        match self {
+            AccessState::Active => 0,
+            AccessState::RevocationScheduled { .. } => 1,
+            AccessState::Revoked { .. } => 2,
+        }
+    }
+
+    /// True if revoked, or if the scheduled revocation has taken effect by `now`
+    pub fn is_revoked_at(&self, now: DateTime) -> bool {
+        match self {
+            AccessState::Active => false,
+            AccessState::RevocationScheduled { until, .. } => until.map(|until| until <= now).unwrap_or(false),
+            AccessState::Revoked { .. } => true,
+        }
+    }
+}
+
+/// Responses that report the state of API access to the subject
+pub trait AccessInfo {
+    fn access_expires_at(&self) -> Option<DateTime>;
+    fn access_revoked_by(&self) -> Option<&RevokeType>;
+
+    /// The subject the token gives access to, None if the response is about another entity
+    fn subject_id(&self) -> Option<&str>;
+
+    fn access_state(&self, clock: &impl Clock) -> AccessState
+    where
+        Self: Sized,
+    {
+        AccessState::from_fields(self.access_expires_at(), self.access_revoked_by(), clock.now())
+    }
+}
+
+impl AccessInfo for ProfileResponse {
+    fn access_expires_at(&self) -> Option<DateTime> {
+        self.access_expires_at
+    }
+
+    fn access_revoked_by(&self) -> Option<&RevokeType> {
+        self.access_revoked_by.as_ref()
+    }
+
+    fn subject_id(&self) -> Option<&str> {
+        Some(&self.id)
+    }
+}
+
+impl AccessInfo for IdentityResponse {
+    fn access_expires_at(&self) -> Option<DateTime> {
+        self.access_expires_at
+    }
+
+    fn access_revoked_by(&self) -> Option<&RevokeType> {
+        self.access_revoked_by.as_ref()
+    }
+
+    fn subject_id(&self) -> Option<&str> {
+        Some(&self.id)
+    }
+}
+
+impl AccessInfo for DependencyIdentityResponse {
+    fn access_expires_at(&self) -> Option<DateTime> {
+        self.access_expires_at
+    }
+
+    fn access_revoked_by(&self) -> Option<&RevokeType> {
+        self.access_revoked_by.as_ref()
+    }
+
+    // `id` is the dependency's, not the subject's
+    fn subject_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Implemented by every response `Client` fetches, so it can track access. None if the response doesn't report
+/// the access of the token's subject, e.g. a dependency identity, whose access fields are the dependency's.
+pub(crate) trait ReportsAccess {
+    fn reported_access(&self, now: DateTime) -> Option<(&str, AccessState)>;
+}
+
+impl<T: AccessInfo> ReportsAccess for T {
+    fn reported_access(&self, now: DateTime) -> Option<(&str, AccessState)> {
+        let subject_id = self.subject_id()?;
+        Some((subject_id, AccessState::from_fields(self.access_expires_at(), self.access_revoked_by(), now)))
+    }
+}
+
+impl ReportsAccess for AccreditationsResponse {
+    fn reported_access(&self, _now: DateTime) -> Option<(&str, AccessState)> {
+        None
+    }
+}
+
+#[derive(Debug)]
+struct TokenAccess {
+    subject_id: Option<EntityId>,
+    state: AccessState,
+}
+
+/// Last known access state per token, updated by `Client` from responses and by the application from webhooks.
+/// Tokens are only kept as SHA-256 hashes. Clones share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct AccessRegistry {
+    tokens: Arc<Mutex<HashMap<String, TokenAccess>>>,
+}
+
+impl AccessRegistry {
+    pub fn new() -> AccessRegistry {
+        AccessRegistry::default()
+    }
+
+    pub fn state(&self, token: &str) -> Option<AccessState> {
+        let tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        tokens.get(&token_key(token)).map(|access| access.state.clone())
+    }
+
+    /// Records the state of a token, keeping the known subject if `subject_id` is None. A state never replaces a stronger
+    /// one: responses omit the access fields until the API knows the revocation time, e.g. after an `AccessRevocationScheduled`
+    /// webhook, and a token's access isn't restored once revoked.
+    pub fn record(&self, token: &str, subject_id: Option<&str>, state: AccessState) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        let access = tokens.entry(token_key(token)).or_insert(TokenAccess {
+            subject_id: None,
+            state: AccessState::Active,
+        });
+        if let Some(subject_id) = subject_id {
+            access.subject_id = Some(subject_id.to_string());
+        }
+        if state.replaces(&access.state) {
+            access.state = state;
+        }
+    }
+
+    /// Marks active tokens of the webhook's entity as scheduled for revocation on `AccessRevocationScheduled`.
+    /// Only tokens already seen in a response of that subject are affected. Returns the number of updated tokens.
+    pub fn record_webhook(&self, webhook: &WebhookData) -> usize {
+        if webhook.event != EventType::AccessRevocationScheduled {
+            return 0;
+        }
+        let mut tokens = self.tokens.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut updated = 0;
+        for access in tokens.values_mut() {
+            if access.subject_id.as_deref() == Some(webhook.entity.id.as_str()) && access.state == AccessState::Active {
+                access.state = AccessState::RevocationScheduled { until: None, by: None };
+                updated += 1;
+            }
+        }
+        updated
+    }
+
+    pub(crate) fn ensure_not_revoked(&self, token: &str, now: DateTime) -> Result<()> {
+        match self.state(token) {
+            Some(AccessState::Revoked { at, by }) => Err(Error::ApiError(ErrorKind::AccessRevoked { at, by })),
+            Some(AccessState::RevocationScheduled { until: Some(until), by }) if until <= now => {
+                Err(Error::ApiError(ErrorKind::AccessRevoked { at: Some(until), by }))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+fn token_key(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::fixtures::*;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    // 2023-01-10 12:00 UTC
+    const JAN_10: i64 = 1673352000;
+    const DAY: i64 = 86400;
+
+    fn response(expires_at: Option<i64>, revoked_by: Option<&str>) -> DependencyIdentityResponse {
+        let response = dependency_identity("holding", "business", business_identity("Holding LLC", vec![], vec![]));
+        serde_json::from_value(with(response, json!({ "access_expires_at": expires_at, "access_revoked_by": revoked_by }))).unwrap()
+    }
+
+    fn scheduled_revocation_webhook() -> WebhookData {
+        serde_json::from_value(json!({
+            "entity": { "id": "subject", "type": "individual" },
+            "event": "access_revocation_scheduled",
+            "scope": "profile",
+            "connecting_business_id": null,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn access_without_access_fields_is_active() {
+        let clock = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(response(None, None).access_state(&clock), AccessState::Active);
+    }
+
+    #[test]
+    fn future_expiry_is_a_scheduled_revocation() {
+        let clock = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(
+            response(Some(JAN_10 + 7 * DAY), Some("subject")).access_state(&clock),
+            AccessState::RevocationScheduled {
+                until: Some(datetime!(2023-01-17 12:00 UTC)),
+                by: Some(RevokeType::Subject),
+            }
+        );
+    }
+
+    #[test]
+    fn past_expiry_is_a_revocation() {
+        let clock = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(
+            response(Some(JAN_10 - DAY), Some("partner")).access_state(&clock),
+            AccessState::Revoked {
+                at: Some(datetime!(2023-01-09 12:00 UTC)),
+                by: Some(RevokeType::Partner),
+            }
+        );
+    }
+
+    #[test]
+    fn revoker_without_expiry_is_a_revocation() {
+        let clock = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(
+            response(None, Some("system")).access_state(&clock),
+            AccessState::Revoked {
+                at: None,
+                by: Some(RevokeType::System)
+            }
+        );
+    }
+
+    #[test]
+    fn dependency_access_is_not_the_tokens() {
+        let now = datetime!(2023-01-10 12:00 UTC);
+
+        assert_eq!(response(Some(JAN_10 - DAY), Some("subject")).reported_access(now), None);
+    }
+
+    #[test]
+    fn subject_identity_access_is_the_tokens() {
+        let now = datetime!(2023-01-10 12:00 UTC);
+        let profile = dependency_identity("subject", "individual", individual_identity("Alice", "Smith"));
+        let profile: IdentityResponse =
+            serde_json::from_value(with(profile, json!({ "access_expires_at": JAN_10 - DAY, "user_providing_for": "self" }))).unwrap();
+
+        assert!(matches!(profile.reported_access(now), Some(("subject", AccessState::Revoked { .. }))));
+    }
+
+    #[test]
+    fn webhooks_schedule_the_revocation_of_the_subjects_tokens() {
+        let registry = AccessRegistry::new();
+        let now = datetime!(2023-01-10 12:00 UTC);
+        registry.record("token", Some("subject"), AccessState::Active);
+        registry.record("other-token", Some("other"), AccessState::Active);
+
+        assert_eq!(registry.record_webhook(&scheduled_revocation_webhook()), 1);
+        assert_eq!(registry.state("token"), Some(AccessState::RevocationScheduled { until: None, by: None }));
+        assert_eq!(registry.state("other-token"), Some(AccessState::Active));
+        assert!(registry.ensure_not_revoked("token", now).is_ok());
+    }
+
+    #[test]
+    fn responses_without_access_fields_keep_a_scheduled_revocation() {
+        let registry = AccessRegistry::new();
+        registry.record("token", Some("subject"), AccessState::Active);
+        registry.record_webhook(&scheduled_revocation_webhook());
+
+        registry.record("token", Some("subject"), AccessState::Active);
+
+        assert_eq!(registry.state("token"), Some(AccessState::RevocationScheduled { until: None, by: None }));
+    }
+
+    #[test]
+    fn tokens_are_refused_once_their_revocation_is_due() {
+        let registry = AccessRegistry::new();
+        let now = datetime!(2023-01-10 12:00 UTC);
+        let scheduled = AccessState::RevocationScheduled {
+            until: Some(now),
+            by: Some(RevokeType::Subject),
+        };
+        registry.record("token", None, scheduled);
+
+        assert!(matches!(registry.ensure_not_revoked("token", now), Err(Error::ApiError(ErrorKind::AccessRevoked { .. }))));
+        assert!(registry.ensure_not_revoked("unknown-token", now).is_ok());
+    }
+
+    #[test]
+    fn registry_keeps_revoked_tokens_revoked() {
+        let registry = AccessRegistry::new();
+        let now = datetime!(2023-01-10 12:00 UTC);
+        let revoked = AccessState::Revoked {
+            at: Some(now),
+            by: Some(RevokeType::Subject),
+        };
+        registry.record("token", Some("subject"), revoked.clone());
+        registry.record(
+            "token",
+            None,
+            AccessState::RevocationScheduled {
+                until: Some(datetime!(2023-01-17 12:00 UTC)),
+                by: None,
+            },
+        );
+        registry.record("token", None, AccessState::Active);
+
+        assert_eq!(registry.state("token"), Some(revoked));
+        assert!(matches!(registry.ensure_not_revoked("token", now), Err(Error::ApiError(ErrorKind::AccessRevoked { .. }))));
+    }
+}
@@ -0,0 +1,298 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::clock::Clock;
+use crate::types::*;
+use json_api_client::types::*;
+
+/// Fields shared by individual and business accreditations
+pub trait AccreditationRecord {
+    fn id(&self) -> &str;
+    fn status(&self) -> &AccreditationStatus;
+    fn expires_at(&self) -> Option<DateTime>;
+    fn created_at(&self) -> DateTime;
+    fn certified_at(&self) -> Option<DateTime>;
+
+    /// Status at `now`: a current accreditation whose `expires_at` has passed is expired
+    fn status_at(&self, now: DateTime) -> AccreditationStatus {
+        match self.status() {
+            AccreditationStatus::Current if self.expires_at().map(|expires_at| expires_at <= now).unwrap_or(false) => AccreditationStatus::Expired,
+            status => status.clone(),
+        }
+    }
+}
+
+impl AccreditationRecord for IndividualAccreditation {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn status(&self) -> &AccreditationStatus {
+        &self.status
+    }
+
+    fn expires_at(&self) -> Option<DateTime> {
+        self.expires_at
+    }
+
+    fn created_at(&self) -> DateTime {
+        self.created_at
+    }
+
+    fn certified_at(&self) -> Option<DateTime> {
+        self.certified_at
+    }
+}
+
+impl AccreditationRecord for BusinessAccreditation {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn status(&self) -> &AccreditationStatus {
+        &self.status
+    }
+
+    fn expires_at(&self) -> Option<DateTime> {
+        self.expires_at
+    }
+
+    fn created_at(&self) -> DateTime {
+        self.created_at
+    }
+
+    fn certified_at(&self) -> Option<DateTime> {
+        self.certified_at
+    }
+}
+
+/// Accreditation state of a subject at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccreditationSummary {
+    /// `until` is None if the accreditation has no expiry date
+    Accredited {
+        until: Option<DateTime>,
+    },
+    PendingReview,
+    ThirdPartyPending,
+    /// The most recent accreditation expired at `at`
+    Expired {
+        at: Option<DateTime>,
+    },
+    Rejected,
+    /// The subject indicated they are not accredited, after any accreditation attempt
+    SelfDeclaredUnaccredited {
+        at: DateTime,
+    },
+    NeverApplied,
+    /// Every accreditation has a status unknown to this crate, `status` is the one of the newest
+    Unknown {
+        status: String,
+    },
+}
+
+impl AccreditationSummary {
+    pub fn is_accredited(&self) -> bool {
+        matches!(self, AccreditationSummary::Accredited { .. })
+    }
+}
+
+impl fmt::Display for AccreditationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccreditationSummary::Accredited { until: Some(until) } => write!(f, "accredited until {}", until),
+            AccreditationSummary::Accredited { until: None } => write!(f, "accredited"),
+            AccreditationSummary::PendingReview => write!(f, "pending review"),
+            AccreditationSummary::ThirdPartyPending => write!(f, "awaiting a third party evaluator"),
+            AccreditationSummary::Expired { at: Some(at) } => write!(f, "expired at {}", at),
+            AccreditationSummary::Expired { at: None } => write!(f, "expired"),
+            AccreditationSummary::Rejected => write!(f, "rejected"),
+            AccreditationSummary::SelfDeclaredUnaccredited { at } => write!(f, "declared unaccredited at {}", at),
+            AccreditationSummary::NeverApplied => write!(f, "never applied"),
+            AccreditationSummary::Unknown { status } => write!(f, "unknown status '{}'", status),
+        }
+    }
+}
+
+impl Accreditations {
+    pub fn records(&self) -> Vec<&dyn AccreditationRecord> {
+        match self {
+            Accreditations::Individual(individual) => individual.iter().map(|a| a as &dyn AccreditationRecord).collect(),
+            Accreditations::Business(business) => business.iter().map(|a| a as &dyn AccreditationRecord).collect(),
+        }
+    }
+}
+
+impl AccreditationsResponse {
+    /// The accreditation that best describes the subject now: a current one (latest expiry first),
+    /// then pending review, third party pending, expired and rejected. Within a status the newest wins.
+    /// Accreditations with a status unknown to this crate are skipped.
+    pub fn best_accreditation(&self, clock: &impl Clock) -> Option<&dyn AccreditationRecord> {
+        let now = clock.now();
+        self.accreditations
+            .records()
+            .into_iter()
+            .filter_map(|record| status_rank(&record.status_at(now)).map(|rank| (rank, record)))
+            .max_by(|(rank_a, a), (rank_b, b)| {
+                rank_a
+                    .cmp(rank_b)
+                    .then_with(|| compare_expiry(a, b))
+                    .then_with(|| a.created_at().cmp(&b.created_at()))
+            })
+            .map(|(_, record)| record)
+    }
+
+    pub fn summary(&self, clock: &impl Clock) -> AccreditationSummary {
+        let now = clock.now();
+        let best = self.best_accreditation(clock);
+        let latest_attempt = self.accreditations.records().iter().map(|record| record.created_at()).max();
+
+        if let Some(indicated_at) = self.indicated_unaccredited {
+            if latest_attempt.map(|created_at| indicated_at > created_at).unwrap_or(true) {
+                return AccreditationSummary::SelfDeclaredUnaccredited { at: indicated_at };
+            }
+        }
+
+        // Only if every status is unknown is there no best accreditation, the newest one is reported then
+        let newest = || self.accreditations.records().into_iter().max_by_key(|record| record.created_at());
+        let Some(record) = best.or_else(newest) else {
+            return AccreditationSummary::NeverApplied;
+        };
+        match record.status_at(now) {
+            AccreditationStatus::Current => AccreditationSummary::Accredited { until: record.expires_at() },
+            AccreditationStatus::Pending => AccreditationSummary::PendingReview,
+            AccreditationStatus::ThirdPartyPending => AccreditationSummary::ThirdPartyPending,
+            AccreditationStatus::Expired => AccreditationSummary::Expired { at: record.expires_at() },
+            AccreditationStatus::Rejected => AccreditationSummary::Rejected,
+            AccreditationStatus::Unknown(status) => AccreditationSummary::Unknown { status },
+        }
+    }
+}
+
+/// Higher is better, None for statuses unknown to this crate
+fn status_rank(status: &AccreditationStatus) -> Option<u8> {
+    match status {
+        AccreditationStatus::Current => Some(5),
+        AccreditationStatus::Pending => Some(4),
+        AccreditationStatus::ThirdPartyPending => Some(3),
+        AccreditationStatus::Expired => Some(2),
+        AccreditationStatus::Rejected => Some(1),
+        AccreditationStatus::Unknown(_) => None,
+    }
+}
+
+/// No expiry sorts after any expiry date
+fn compare_expiry(a: &&dyn AccreditationRecord, b: &&dyn AccreditationRecord) -> Ordering {
+    match (a.expires_at(), b.expires_at()) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::fixtures::*;
+    use time::macros::datetime;
+
+    // 2023-01-10 12:00 UTC
+    const JAN_10: i64 = 1673352000;
+    const DAY: i64 = 86400;
+
+    fn response(accreditations: Vec<serde_json::Value>, indicated_unaccredited: Option<i64>) -> AccreditationsResponse {
+        serde_json::from_value(accreditations_response("individual", accreditations, indicated_unaccredited)).unwrap()
+    }
+
+    fn history() -> AccreditationsResponse {
+        response(
+            vec![
+                individual_accreditation("rejected", "rejected", JAN_10 - 90 * DAY, None),
+                individual_accreditation("current", "current", JAN_10 - 30 * DAY, Some(JAN_10 + 60 * DAY)),
+                individual_accreditation("pending", "pending", JAN_10 - DAY, None),
+            ],
+            None,
+        )
+    }
+
+    fn expired() -> serde_json::Value {
+        individual_accreditation("expired", "current", JAN_10 - 400 * DAY, Some(JAN_10 - 35 * DAY))
+    }
+
+    #[test]
+    fn current_accreditation_wins_over_newer_attempts() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(history().best_accreditation(&now).unwrap().id(), "current");
+        assert_eq!(
+            history().summary(&now),
+            AccreditationSummary::Accredited {
+                until: Some(datetime!(2023-03-11 12:00 UTC))
+            }
+        );
+    }
+
+    #[test]
+    fn pending_attempt_wins_once_the_current_accreditation_expired() {
+        let later = FixedClock(datetime!(2023-03-12 12:00 UTC));
+
+        assert_eq!(history().best_accreditation(&later).unwrap().id(), "pending");
+        assert_eq!(history().summary(&later), AccreditationSummary::PendingReview);
+    }
+
+    #[test]
+    fn subjects_without_accreditations_never_applied() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(response(vec![], None).summary(&now), AccreditationSummary::NeverApplied);
+    }
+
+    #[test]
+    fn declarations_without_accreditations_are_self_declared_unaccredited() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(
+            response(vec![], Some(JAN_10 - DAY)).summary(&now),
+            AccreditationSummary::SelfDeclaredUnaccredited {
+                at: datetime!(2023-01-09 12:00 UTC)
+            }
+        );
+    }
+
+    #[test]
+    fn expired_accreditations_report_their_expiry() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+
+        assert_eq!(
+            response(vec![expired()], None).summary(&now),
+            AccreditationSummary::Expired {
+                at: Some(datetime!(2022-12-06 12:00 UTC))
+            }
+        );
+    }
+
+    #[test]
+    fn declarations_older_than_the_latest_attempt_are_ignored() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+        let third_party = individual_accreditation("third-party", "third_party_pending", JAN_10 - DAY, None);
+
+        assert_eq!(response(vec![expired(), third_party], Some(JAN_10 - 2 * DAY)).summary(&now), AccreditationSummary::ThirdPartyPending);
+    }
+
+    #[test]
+    fn unknown_statuses_are_not_reported_as_never_applied() {
+        let now = FixedClock(datetime!(2023-01-10 12:00 UTC));
+        let response = response(
+            vec![
+                individual_accreditation("old", "withdrawn", JAN_10 - 90 * DAY, None),
+                individual_accreditation("new", "on_hold", JAN_10 - DAY, None),
+            ],
+            None,
+        );
+
+        assert!(response.best_accreditation(&now).is_none());
+        assert_eq!(response.summary(&now), AccreditationSummary::Unknown { status: "on_hold".to_string() });
+    }
+}
@@ -0,0 +1,313 @@
+use serde::Serialize;
+
+use crate::countries::*;
+use crate::types::*;
+use crate::values::InvalidValue;
+
+/// Countries writing the postal code before the city, e.g. `10115 Berlin`
+const POSTAL_CODE_FIRST: [&str; 14] = ["AT", "BE", "CH", "DE", "DK", "ES", "FI", "FR", "IT", "LU", "NL", "NO", "PT", "SE"];
+
+/// Validates a postal code for a country, returning it in its usual format, e.g. `SW1A 1AA`.
+/// Countries without a known format accept any code of up to 10 letters, digits, spaces and dashes.
+pub fn normalize_postal_code(country: &str, postal_code: &str) -> Result<String, InvalidValue> {
+    let invalid = InvalidValue("postal code");
+    let compact: String = postal_code.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<String>().to_uppercase();
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    match country.to_uppercase().as_str() {
+        "US" => match compact.len() {
+            5 if digits(&compact) => Ok(compact),
+            9 if digits(&compact) => Ok(format!("{}-{}", &compact[..5], &compact[5..])),
+            _ => Err(invalid),
+        },
+        "CA" => {
+            let valid = compact.len() == 6
+                && compact
+                    .chars()
+                    .enumerate()
+                    .all(|(i, c)| if i % 2 == 0 { c.is_ascii_alphabetic() } else { c.is_ascii_digit() });
+            if valid {
+                Ok(format!("{} {}", &compact[..3], &compact[3..]))
+            } else {
+                Err(invalid)
+            }
+        },
+        "GB" => {
+            // Outward code of 2-4 characters starting with a letter, inward code of a digit and two letters
+            let valid = (5..=7).contains(&compact.len())
+                && compact.chars().all(|c| c.is_ascii_alphanumeric())
+                && compact.starts_with(|c: char| c.is_ascii_alphabetic())
+                && compact[compact.len() - 3..]
+                    .chars()
+                    .enumerate()
+                    .all(|(i, c)| if i == 0 { c.is_ascii_digit() } else { c.is_ascii_alphabetic() });
+            if valid {
+                Ok(format!("{} {}", &compact[..compact.len() - 3], &compact[compact.len() - 3..]))
+            } else {
+                Err(invalid)
+            }
+        },
+        "DE" | "FR" | "IT" | "ES" | "FI" if compact.len() == 5 && digits(&compact) => Ok(compact),
+        "DE" | "FR" | "IT" | "ES" | "FI" => Err(invalid),
+        "AT" | "BE" | "CH" | "DK" | "LU" | "NO" if compact.len() == 4 && digits(&compact) => Ok(compact),
+        "AT" | "BE" | "CH" | "DK" | "LU" | "NO" => Err(invalid),
+        _ => {
+            let postal_code = postal_code.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+            let valid = !postal_code.is_empty() && postal_code.len() <= 10 && postal_code.chars().all(|c| c.is_ascii_alphanumeric() || c == ' ' || c == '-');
+            if valid {
+                Ok(postal_code)
+            } else {
+                Err(invalid)
+            }
+        },
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum AddressIssue {
+    /// The postal code doesn't match the country's format, it is kept as received
+    InvalidPostalCode,
+    UnknownUsState {
+        state: String,
+    },
+    /// `region` and `state` are both set and differ, `state` is used
+    ConflictingSubdivision {
+        region: String,
+        state: String,
+    },
+}
+
+/// A `Location` with `region` and `state` merged into `subdivision`, US states as two letter codes
+/// and postal codes in their usual format
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NormalizedAddress {
+    /// Non-empty street lines
+    pub street: Vec<String>,
+    pub city: String,
+    pub subdivision: Option<String>,
+    pub postal_code: String,
+    /// Country code as sent by the API
+    pub country: String,
+    pub issues: Vec<AddressIssue>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressField {
+    Country,
+    Subdivision,
+    City,
+    PostalCode,
+    Street,
+}
+
+impl Location {
+    pub fn normalize(&self) -> NormalizedAddress {
+        let country = country_code(&self.country).unwrap_or_default();
+        let mut issues = Vec::new();
+
+        let street = [&self.address_one, &self.address_two]
+            .into_iter()
+            .map(|line| collapse_whitespace(line))
+            .filter(|line| !line.is_empty())
+            .collect();
+
+        let canonical = |subdivision: &str| {
+            if country == "US" {
+                canonical_us_state(subdivision).map(str::to_string)
+            } else {
+                Some(subdivision.to_string())
+            }
+        };
+        let region = self.region.as_deref().map(collapse_whitespace).filter(|region| !region.is_empty());
+        let state = self.state.as_deref().map(collapse_whitespace).filter(|state| !state.is_empty());
+        let subdivision = match (region, state) {
+            (Some(region), Some(state)) => {
+                let same = match (canonical(&region), canonical(&state)) {
+                    (Some(region), Some(state)) => region.eq_ignore_ascii_case(&state),
+                    _ => false,
+                };
+                if !same {
+                    issues.push(AddressIssue::ConflictingSubdivision { region, state: state.clone() });
+                }
+                Some(state)
+            },
+            (region, state) => state.or(region),
+        };
+        let subdivision = subdivision.map(|subdivision| match canonical(&subdivision) {
+            Some(canonical) => canonical,
+            None => {
+                issues.push(AddressIssue::UnknownUsState { state: subdivision.clone() });
+                subdivision
+            },
+        });
+
+        let postal_code = normalize_postal_code(&country, &self.postal_code).unwrap_or_else(|_| {
+            issues.push(AddressIssue::InvalidPostalCode);
+            self.postal_code.trim().to_string()
+        });
+
+        NormalizedAddress {
+            street,
+            city: collapse_whitespace(&self.city),
+            subdivision,
+            postal_code,
+            country,
+            issues,
+        }
+    }
+}
+
+impl NormalizedAddress {
+    /// Address lines in the usual order for the country, ending with the country code
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = self.street.clone();
+        let subdivision = self.subdivision.as_deref().unwrap_or_default();
+        let join = |parts: &[&str]| parts.iter().filter(|part| !part.is_empty()).copied().collect::<Vec<_>>().join(" ");
+
+        match self.country.as_str() {
+            "US" | "CA" | "AU" => {
+                let city = if self.city.is_empty() { String::new() } else { format!("{},", self.city) };
+                lines.push(join(&[&city, subdivision, &self.postal_code]).trim_end_matches(',').to_string());
+            },
+            "GB" => {
+                lines.push(self.city.to_uppercase());
+                lines.push(subdivision.to_string());
+                lines.push(self.postal_code.clone());
+            },
+            country if POSTAL_CODE_FIRST.contains(&country) => {
+                lines.push(join(&[&self.postal_code, &self.city]));
+                lines.push(subdivision.to_string());
+            },
+            _ => {
+                lines.push(join(&[&self.city, subdivision]));
+                lines.push(self.postal_code.clone());
+            },
+        }
+        lines.push(self.country.clone());
+        lines.retain(|line| !line.is_empty());
+        lines
+    }
+
+    /// `lines` joined by newlines
+    pub fn render(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    /// Fields that differ, ignoring case, punctuation and whitespace
+    pub fn mismatches(&self, other: &NormalizedAddress) -> Vec<AddressField> {
+        let same = |a: &str, b: &str| comparable(a) == comparable(b);
+        let mut mismatches = Vec::new();
+        if self.country != other.country {
+            mismatches.push(AddressField::Country);
+        }
+        if !same(self.subdivision.as_deref().unwrap_or_default(), other.subdivision.as_deref().unwrap_or_default()) {
+            mismatches.push(AddressField::Subdivision);
+        }
+        if !same(&self.city, &other.city) {
+            mismatches.push(AddressField::City);
+        }
+        if !same(&self.postal_code, &other.postal_code) {
+            mismatches.push(AddressField::PostalCode);
+        }
+        if !same(&self.street.join(" "), &other.street.join(" ")) {
+            mismatches.push(AddressField::Street);
+        }
+        mismatches
+    }
+}
+
+/// Fields in which the residence and domicile of an individual differ
+pub fn residence_domicile_mismatches(details: &IndividualIdentityDetails) -> Vec<AddressField> {
+    details.residence_location.normalize().mismatches(&details.domicile_location.normalize())
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn comparable(value: &str) -> String {
+    value.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+
+    fn normalized(country: &str, overrides: serde_json::Value) -> NormalizedAddress {
+        let location: Location = serde_json::from_value(with(location(country), overrides)).unwrap();
+        location.normalize()
+    }
+
+    fn mismatches(domicile_overrides: serde_json::Value) -> Vec<AddressField> {
+        let identity = with(individual_identity("Alice", "Smith"), json!({ "domicile_location": domicile_overrides }));
+        residence_domicile_mismatches(&serde_json::from_value(identity).unwrap())
+    }
+
+    #[test]
+    fn us_state_names_are_normalized_to_codes() {
+        let us = normalized("US", json!({ "state": "illinois", "region": "IL" }));
+
+        assert_eq!(us.subdivision.as_deref(), Some("IL"));
+        assert!(us.issues.is_empty());
+    }
+
+    #[test]
+    fn us_addresses_render_with_zip_plus_four() {
+        let us = normalized("US", json!({ "postal_code": "627011234" }));
+
+        assert_eq!(us.render(), "1 Main St\nSpringfield, IL 62701-1234\nUS");
+    }
+
+    #[test]
+    fn conflicting_state_and_region_are_reported() {
+        let us = normalized("US", json!({ "state": "IL", "region": "Ohio" }));
+
+        assert_eq!(
+            us.issues,
+            vec![AddressIssue::ConflictingSubdivision {
+                region: "Ohio".to_string(),
+                state: "IL".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn invalid_postal_codes_are_reported() {
+        let us = normalized("US", json!({ "postal_code": "6270" }));
+
+        assert_eq!(us.issues, vec![AddressIssue::InvalidPostalCode]);
+    }
+
+    #[test]
+    fn gb_addresses_put_the_town_in_capitals_before_the_postcode() {
+        let gb = normalized("GB", json!({ "city": "London", "state": null, "postal_code": "sw1a1aa" }));
+
+        assert_eq!(gb.lines(), vec!["1 Main St", "LONDON", "SW1A 1AA", "GB"]);
+    }
+
+    #[test]
+    fn de_addresses_put_the_postal_code_before_the_city() {
+        let de = normalized("DE", json!({ "city": "Berlin", "state": null, "region": "Berlin", "postal_code": "10115" }));
+
+        assert_eq!(de.lines(), vec!["1 Main St", "10115 Berlin", "Berlin", "DE"]);
+    }
+
+    #[test]
+    fn ca_postal_codes_are_spaced_and_uppercased() {
+        assert_eq!(normalize_postal_code("CA", "k1a0b1"), Ok("K1A 0B1".to_string()));
+    }
+
+    #[test]
+    fn differently_written_addresses_match() {
+        assert!(mismatches(json!({ "address_one": "1 main st.", "state": "Illinois" })).is_empty());
+    }
+
+    #[test]
+    fn differing_address_fields_are_reported() {
+        assert_eq!(mismatches(json!({ "city": "Chicago", "postal_code": "60601" })), vec![AddressField::City, AddressField::PostalCode]);
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::accreditation::*;
+use crate::clock::Clock;
+use crate::types::*;
+use crate::{Client, Result};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AssertionFailure {
+    UnknownBusinessType(String),
+    UnknownAssertionType(String),
+    /// The business type cannot qualify with this assertion type, e.g. an irrevocable trust through its owners
+    AssertionNotAllowed {
+        business_type: BusinessType,
+        assertion_type: BusinessAssertionType,
+    },
+    /// Accreditation through owners, but the business lists no beneficial owners
+    NoOwners,
+    /// The owner at `index` has no ID, so their accreditation can't be looked up
+    OwnerWithoutId {
+        index: usize,
+    },
+    OwnerAccreditationMissing {
+        owner_id: EntityId,
+    },
+    OwnerNotAccredited {
+        owner_id: EntityId,
+        summary: AccreditationSummary,
+    },
+    /// A revocable trust relies on its grantors, who must be individuals
+    GrantorNotIndividual {
+        owner_id: Option<EntityId>,
+        index: usize,
+    },
+}
+
+impl fmt::Display for AssertionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssertionFailure::UnknownBusinessType(business_type) => write!(f, "unknown business type '{}'", business_type),
+            AssertionFailure::UnknownAssertionType(assertion_type) => write!(f, "unknown assertion type '{}'", assertion_type),
+            AssertionFailure::AssertionNotAllowed { business_type, assertion_type } => {
+                write!(f, "{} cannot be accredited by {}", business_type, assertion_type)
+            },
+            AssertionFailure::NoOwners => write!(f, "no beneficial owners to rely on"),
+            AssertionFailure::OwnerWithoutId { index } => write!(f, "owner at index {} has no ID", index),
+            AssertionFailure::OwnerAccreditationMissing { owner_id } => write!(f, "accreditation of owner '{}' not provided", owner_id),
+            AssertionFailure::OwnerNotAccredited { owner_id, summary } => write!(f, "owner '{}' is not accredited: {}", owner_id, summary),
+            AssertionFailure::GrantorNotIndividual { index, .. } => write!(f, "grantor at index {} is not an individual", index),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssertionReport {
+    pub accreditation_id: String,
+    pub business_type: BusinessType,
+    pub assertion_type: BusinessAssertionType,
+    /// Summaries of the owners whose accreditation was checked, for `AccreditedOwners`
+    pub owners: Vec<(EntityId, AccreditationSummary)>,
+    pub failures: Vec<AssertionFailure>,
+}
+
+impl AssertionReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Checks that the assertion type of a business accreditation is consistent with the business type.
+/// For `AccreditedOwners` every direct beneficial owner must currently be accredited according to
+/// `owner_accreditations` (keyed by owner ID, e.g. fetched with each owner's token).
+pub fn check_business_assertion(
+    accreditation: &BusinessAccreditation,
+    details: &BusinessIdentityDetails,
+    owner_accreditations: &HashMap<EntityId, AccreditationsResponse>,
+    clock: &impl Clock,
+) -> AssertionReport {
+    let mut report = AssertionReport {
+        accreditation_id: accreditation.id.clone(),
+        business_type: details.business_type.clone(),
+        assertion_type: accreditation.assertion_type.clone(),
+        owners: Vec::new(),
+        failures: Vec::new(),
+    };
+
+    if let BusinessType::Unknown(business_type) = &details.business_type {
+        report.failures.push(AssertionFailure::UnknownBusinessType(business_type.clone()));
+    }
+    match &accreditation.assertion_type {
+        BusinessAssertionType::Worth | BusinessAssertionType::EvaluatorAssertion => {},
+        BusinessAssertionType::Unknown(assertion_type) => report.failures.push(AssertionFailure::UnknownAssertionType(assertion_type.clone())),
+        BusinessAssertionType::AccreditedOwners => check_accredited_owners(details, owner_accreditations, clock, &mut report),
+    }
+    report
+}
+
+impl Client {
+    /// Fetches the accreditations of the direct owners with a token in `owner_tokens` (keyed by owner ID), then runs
+    /// `check_business_assertion`. Owners without a token are reported as `OwnerAccreditationMissing`.
+    pub async fn check_business_assertion(
+        &self,
+        accreditation: &BusinessAccreditation,
+        details: &BusinessIdentityDetails,
+        owner_tokens: &HashMap<EntityId, String>,
+    ) -> Result<AssertionReport> {
+        let mut owner_accreditations = HashMap::new();
+        if accreditation.assertion_type == BusinessAssertionType::AccreditedOwners {
+            for owner_id in details.direct_beneficial_owners.iter().filter_map(|owner| owner.id.as_ref()) {
+                if let Some(token) = owner_tokens.get(owner_id) {
+                    owner_accreditations.insert(owner_id.clone(), self.get_accreditations(token).await?);
+                }
+            }
+        }
+        Ok(check_business_assertion(accreditation, details, &owner_accreditations, &self.clock))
+    }
+}
+
+fn check_accredited_owners(
+    details: &BusinessIdentityDetails,
+    owner_accreditations: &HashMap<EntityId, AccreditationsResponse>,
+    clock: &impl Clock,
+    report: &mut AssertionReport,
+) {
+    // 501(c)(3) organizations and irrevocable trusts qualify by their assets or trustee, not their owners
+    if matches!(details.business_type, BusinessType::IrrevocableTrust | BusinessType::PublicCharity | BusinessType::PrivateFoundation) {
+        report.failures.push(AssertionFailure::AssertionNotAllowed {
+            business_type: details.business_type.clone(),
+            assertion_type: BusinessAssertionType::AccreditedOwners,
+        });
+        return;
+    }
+    if details.direct_beneficial_owners.is_empty() {
+        report.failures.push(AssertionFailure::NoOwners);
+        return;
+    }
+
+    for (index, owner) in details.direct_beneficial_owners.iter().enumerate() {
+        if details.business_type == BusinessType::RevocableTrust && owner.reference_type != EntityType::Individual {
+            report.failures.push(AssertionFailure::GrantorNotIndividual {
+                owner_id: owner.id.clone(),
+                index,
+            });
+        }
+
+        let Some(owner_id) = &owner.id else {
+            report.failures.push(AssertionFailure::OwnerWithoutId { index });
+            continue;
+        };
+        let Some(accreditations) = owner_accreditations.get(owner_id) else {
+            report.failures.push(AssertionFailure::OwnerAccreditationMissing { owner_id: owner_id.clone() });
+            continue;
+        };
+        let summary = accreditations.summary(clock);
+        if !summary.is_accredited() {
+            report.failures.push(AssertionFailure::OwnerNotAccredited {
+                owner_id: owner_id.clone(),
+                summary: summary.clone(),
+            });
+        }
+        report.owners.push((owner_id.clone(), summary));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::fixtures::*;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    // 2023-01-10 12:00 UTC
+    const JAN_10: i64 = 1673352000;
+    const DAY: i64 = 86400;
+
+    fn business(business_type: &str, owners: Vec<serde_json::Value>) -> BusinessIdentityDetails {
+        serde_json::from_value(with(business_identity("Holding LLC", owners, vec![]), json!({ "business_type": business_type }))).unwrap()
+    }
+
+    fn accredited_owners() -> BusinessAccreditation {
+        serde_json::from_value(json!({
+            "id": "business-accreditation",
+            "status": "current",
+            "expires_at": JAN_10 + 90 * DAY,
+            "assertion_type": "accredited-owners",
+            "created_at": JAN_10,
+            "certified_at": JAN_10,
+            "name": "Holding LLC",
+            "documents": [],
+        }))
+        .unwrap()
+    }
+
+    fn owner_accreditation(status: &str) -> AccreditationsResponse {
+        let accreditation = individual_accreditation("owner-accreditation", status, JAN_10 - DAY, Some(JAN_10 + 90 * DAY));
+        serde_json::from_value(accreditations_response("individual", vec![accreditation], None)).unwrap()
+    }
+
+    fn check_partnership(owners: &[(&str, &str)]) -> AssertionReport {
+        let details = business("Partnership LLC", vec![individual_owner("alice", "Alice", "Smith", 50), individual_owner("bob", "Bob", "Jones", 50)]);
+        let owners = owners.iter().map(|(id, status)| (id.to_string(), owner_accreditation(status))).collect();
+        check_business_assertion(&accredited_owners(), &details, &owners, &FixedClock(datetime!(2023-01-10 12:00 UTC)))
+    }
+
+    #[test]
+    fn owners_without_accreditation_fail_accredited_owners() {
+        let report = check_partnership(&[("alice", "current")]);
+
+        assert!(!report.passed());
+        assert_eq!(report.failures, vec![AssertionFailure::OwnerAccreditationMissing { owner_id: "bob".to_string() }]);
+    }
+
+    #[test]
+    fn owners_not_accredited_fail_accredited_owners() {
+        let report = check_partnership(&[("alice", "current"), ("bob", "pending")]);
+
+        assert_eq!(
+            report.failures,
+            vec![AssertionFailure::OwnerNotAccredited {
+                owner_id: "bob".to_string(),
+                summary: AccreditationSummary::PendingReview,
+            }]
+        );
+        assert_eq!(report.failures[0].to_string(), "owner 'bob' is not accredited: pending review");
+    }
+
+    #[test]
+    fn accredited_owners_pass_when_every_owner_is_accredited() {
+        assert!(check_partnership(&[("alice", "current"), ("bob", "current")]).passed());
+    }
+
+    fn check(details: &BusinessIdentityDetails) -> AssertionReport {
+        let owners = HashMap::from([("holding".to_string(), owner_accreditation("current"))]);
+        check_business_assertion(&accredited_owners(), details, &owners, &FixedClock(datetime!(2023-01-10 12:00 UTC)))
+    }
+
+    #[test]
+    fn assertions_not_allowed_for_the_business_type_fail() {
+        let report = check(&business("Irrevocable Trust", vec![]));
+
+        assert_eq!(
+            report.failures,
+            vec![AssertionFailure::AssertionNotAllowed {
+                business_type: BusinessType::IrrevocableTrust,
+                assertion_type: BusinessAssertionType::AccreditedOwners,
+            }]
+        );
+        assert_eq!(report.failures[0].to_string(), "Irrevocable Trust cannot be accredited by accredited-owners");
+    }
+
+    #[test]
+    fn revocable_trust_grantors_must_be_individuals() {
+        let report = check(&business("Revocable Trust", vec![business_owner("holding", "Holding LLC", 100)]));
+
+        assert_eq!(
+            report.failures,
+            vec![AssertionFailure::GrantorNotIndividual {
+                owner_id: Some("holding".to_string()),
+                index: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_business_types_fail() {
+        let report = check(&business("Cooperative", vec![]));
+
+        assert_eq!(report.failures, vec![AssertionFailure::UnknownBusinessType("Cooperative".to_string()), AssertionFailure::NoOwners]);
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+use time::OffsetDateTime;
+
+use json_api_client::types::*;
+
+/// Source of the current time, so time dependent checks can be tested and replayed
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime;
+
+    /// Current date in UTC
+    fn today(&self) -> Date {
+        self.now().date()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime {
+        OffsetDateTime::now_utc()
+    }
+}
+
+/// Always returns the same time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedClock(pub DateTime);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime {
+        self.0
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> DateTime {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> DateTime {
+        (**self).now()
+    }
+}
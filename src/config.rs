@@ -0,0 +1,65 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::{Error, Result};
+
+/// Codes such as countries or US states as sent by the API, e.g. `US`. Matched ignoring case and surrounding whitespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct CodeList(pub Vec<String>);
+
+impl CodeList {
+    pub fn contains(&self, code: &str) -> bool {
+        self.0.iter().any(|listed| listed.trim().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// True if `code` is listed, or the list is empty
+    pub fn allows(&self, code: &str) -> bool {
+        self.0.is_empty() || self.contains(code)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<S: Into<String>> FromIterator<S> for CodeList {
+    fn from_iter<I: IntoIterator<Item = S>>(codes: I) -> Self {
+        CodeList(codes.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Parses configuration in TOML, `what` names it in errors
+pub(crate) fn from_toml<T: DeserializeOwned>(what: &str, content: &str) -> Result<T> {
+    toml::from_str(content).map_err(|e| Error::ConfigError(format!("{}: {}", what, e)))
+}
+
+/// Parses configuration in JSON, `what` names it in errors
+pub(crate) fn from_json<T: DeserializeOwned>(what: &str, content: &str) -> Result<T> {
+    serde_json::from_str(content).map_err(|e| Error::ConfigError(format!("{}: {}", what, e)))
+}
+
+/// Loads JSON files by their `.json` extension, anything else as TOML
+pub(crate) fn from_file<T: DeserializeOwned>(what: &str, path: &Path) -> Result<T> {
+    let content = fs::read_to_string(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("json") => from_json(what, &content),
+        _ => from_toml(what, &content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_lists_ignore_case_and_whitespace() {
+        let list: CodeList = from_json("test", r#"[" kp", "IR"]"#).unwrap();
+        assert!(list.contains("KP") && list.contains("ir "));
+        assert!(!list.allows("US"));
+        assert!(CodeList::default().allows("US"));
+        assert!(matches!(from_toml::<CodeList>("test", "KP"), Err(Error::ConfigError(message)) if message.starts_with("test: ")));
+    }
+}
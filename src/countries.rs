@@ -0,0 +1,313 @@
+use json_api_client::types::CountryCode;
+
+/// The code of a country as sent by the API, e.g. `US`
+pub(crate) fn country_code(country: &CountryCode) -> Option<String> {
+    serde_json::to_value(country).ok()?.as_str().map(str::to_string)
+}
+
+/// ISO 3166-1 alpha-2 code, in uppercase, of a country given by name or demonym, e.g. `The Netherlands` or `Dutch`.
+/// Other two letter values are taken as codes.
+pub(crate) fn country_code_by_name(name: &str) -> Option<String> {
+    let name: String = name.chars().filter(|c| *c != '.').collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = name.strip_prefix("the ").or_else(|| name.strip_prefix("The ")).unwrap_or(&name);
+    match COUNTRY_NAMES.iter().find(|(_, names)| names.iter().any(|known| known.eq_ignore_ascii_case(name))) {
+        Some((code, _)) => Some(code.to_string()),
+        None if name.len() == 2 && name.chars().all(|c| c.is_ascii_alphabetic()) => Some(name.to_ascii_uppercase()),
+        None => None,
+    }
+}
+
+/// Country names and demonyms, as e.g. screening providers report nationalities, by ISO 3166-1 alpha-2 code
+const COUNTRY_NAMES: &[(&str, &[&str])] = &[
+    ("AF", &["Afghanistan", "Afghan"]),
+    ("AL", &["Albania", "Albanian"]),
+    ("DZ", &["Algeria", "Algerian"]),
+    ("AD", &["Andorra", "Andorran"]),
+    ("AO", &["Angola", "Angolan"]),
+    ("AG", &["Antigua and Barbuda", "Antiguan", "Barbudan"]),
+    ("AR", &["Argentina", "Argentine", "Argentinian"]),
+    ("AM", &["Armenia", "Armenian"]),
+    ("AU", &["Australia", "Australian"]),
+    ("AT", &["Austria", "Austrian"]),
+    ("AZ", &["Azerbaijan", "Azerbaijani"]),
+    ("BS", &["Bahamas", "Bahamian"]),
+    ("BH", &["Bahrain", "Bahraini"]),
+    ("BD", &["Bangladesh", "Bangladeshi"]),
+    ("BB", &["Barbados", "Barbadian"]),
+    ("BY", &["Belarus", "Belarusian"]),
+    ("BE", &["Belgium", "Belgian"]),
+    ("BZ", &["Belize", "Belizean"]),
+    ("BJ", &["Benin", "Beninese"]),
+    ("BT", &["Bhutan", "Bhutanese"]),
+    ("BO", &["Bolivia", "Bolivian"]),
+    ("BA", &["Bosnia and Herzegovina", "Bosnian"]),
+    ("BW", &["Botswana", "Motswana", "Batswana"]),
+    ("BR", &["Brazil", "Brazilian"]),
+    ("BN", &["Brunei", "Bruneian"]),
+    ("BG", &["Bulgaria", "Bulgarian"]),
+    ("BF", &["Burkina Faso", "Burkinabe"]),
+    ("BI", &["Burundi", "Burundian"]),
+    ("CV", &["Cabo Verde", "Cape Verde", "Cape Verdean"]),
+    ("KH", &["Cambodia", "Cambodian"]),
+    ("CM", &["Cameroon", "Cameroonian"]),
+    ("CA", &["Canada", "Canadian"]),
+    ("CF", &["Central African Republic", "Central African"]),
+    ("TD", &["Chad", "Chadian"]),
+    ("CL", &["Chile", "Chilean"]),
+    ("CN", &["China", "Chinese"]),
+    ("CO", &["Colombia", "Colombian"]),
+    ("KM", &["Comoros", "Comoran"]),
+    ("CG", &["Congo", "Republic of the Congo"]),
+    ("CD", &["Democratic Republic of the Congo", "DR Congo"]),
+    ("CR", &["Costa Rica", "Costa Rican"]),
+    ("CI", &["Cote d'Ivoire", "Ivory Coast", "Ivorian"]),
+    ("HR", &["Croatia", "Croatian"]),
+    ("CU", &["Cuba", "Cuban"]),
+    ("CY", &["Cyprus", "Cypriot"]),
+    ("CZ", &["Czechia", "Czech Republic", "Czech"]),
+    ("DK", &["Denmark", "Danish"]),
+    ("DJ", &["Djibouti", "Djiboutian"]),
+    ("DM", &["Dominica"]),
+    ("DO", &["Dominican Republic"]),
+    ("EC", &["Ecuador", "Ecuadorian"]),
+    ("EG", &["Egypt", "Egyptian"]),
+    ("SV", &["El Salvador", "Salvadoran"]),
+    ("GQ", &["Equatorial Guinea", "Equatoguinean"]),
+    ("ER", &["Eritrea", "Eritrean"]),
+    ("EE", &["Estonia", "Estonian"]),
+    ("SZ", &["Eswatini", "Swaziland", "Swazi"]),
+    ("ET", &["Ethiopia", "Ethiopian"]),
+    ("FJ", &["Fiji", "Fijian"]),
+    ("FI", &["Finland", "Finnish"]),
+    ("FR", &["France", "French"]),
+    ("GA", &["Gabon", "Gabonese"]),
+    ("GM", &["Gambia", "Gambian"]),
+    ("GE", &["Georgia", "Georgian"]),
+    ("DE", &["Germany", "German"]),
+    ("GH", &["Ghana", "Ghanaian"]),
+    ("GR", &["Greece", "Greek"]),
+    ("GD", &["Grenada", "Grenadian"]),
+    ("GT", &["Guatemala", "Guatemalan"]),
+    ("GN", &["Guinea", "Guinean"]),
+    ("GW", &["Guinea-Bissau", "Bissau-Guinean"]),
+    ("GY", &["Guyana", "Guyanese"]),
+    ("HT", &["Haiti", "Haitian"]),
+    ("HN", &["Honduras", "Honduran"]),
+    ("HK", &["Hong Kong", "Hong Konger"]),
+    ("HU", &["Hungary", "Hungarian"]),
+    ("IS", &["Iceland", "Icelandic"]),
+    ("IN", &["India", "Indian"]),
+    ("ID", &["Indonesia", "Indonesian"]),
+    ("IR", &["Iran", "Islamic Republic of Iran", "Iranian"]),
+    ("IQ", &["Iraq", "Iraqi"]),
+    ("IE", &["Ireland", "Irish"]),
+    ("IL", &["Israel", "Israeli"]),
+    ("IT", &["Italy", "Italian"]),
+    ("JM", &["Jamaica", "Jamaican"]),
+    ("JP", &["Japan", "Japanese"]),
+    ("JO", &["Jordan", "Jordanian"]),
+    ("KZ", &["Kazakhstan", "Kazakh", "Kazakhstani"]),
+    ("KE", &["Kenya", "Kenyan"]),
+    ("KI", &["Kiribati", "I-Kiribati"]),
+    ("KP", &["North Korea", "Democratic People's Republic of Korea", "North Korean"]),
+    ("KR", &["South Korea", "Republic of Korea", "South Korean"]),
+    ("XK", &["Kosovo", "Kosovar"]),
+    ("KW", &["Kuwait", "Kuwaiti"]),
+    ("KG", &["Kyrgyzstan", "Kyrgyz"]),
+    ("LA", &["Laos", "Lao", "Laotian"]),
+    ("LV", &["Latvia", "Latvian"]),
+    ("LB", &["Lebanon", "Lebanese"]),
+    ("LS", &["Lesotho", "Basotho", "Mosotho"]),
+    ("LR", &["Liberia", "Liberian"]),
+    ("LY", &["Libya", "Libyan"]),
+    ("LI", &["Liechtenstein", "Liechtensteiner"]),
+    ("LT", &["Lithuania", "Lithuanian"]),
+    ("LU", &["Luxembourg", "Luxembourgish", "Luxembourger"]),
+    ("MO", &["Macao", "Macau", "Macanese"]),
+    ("MG", &["Madagascar", "Malagasy"]),
+    ("MW", &["Malawi", "Malawian"]),
+    ("MY", &["Malaysia", "Malaysian"]),
+    ("MV", &["Maldives", "Maldivian"]),
+    ("ML", &["Mali", "Malian"]),
+    ("MT", &["Malta", "Maltese"]),
+    ("MH", &["Marshall Islands", "Marshallese"]),
+    ("MR", &["Mauritania", "Mauritanian"]),
+    ("MU", &["Mauritius", "Mauritian"]),
+    ("MX", &["Mexico", "Mexican"]),
+    ("FM", &["Micronesia", "Micronesian"]),
+    ("MD", &["Moldova", "Moldovan"]),
+    ("MC", &["Monaco", "Monegasque"]),
+    ("MN", &["Mongolia", "Mongolian"]),
+    ("ME", &["Montenegro", "Montenegrin"]),
+    ("MA", &["Morocco", "Moroccan"]),
+    ("MZ", &["Mozambique", "Mozambican"]),
+    ("MM", &["Myanmar", "Burma", "Burmese"]),
+    ("NA", &["Namibia", "Namibian"]),
+    ("NR", &["Nauru", "Nauruan"]),
+    ("NP", &["Nepal", "Nepali", "Nepalese"]),
+    ("NL", &["Netherlands", "Dutch"]),
+    ("NZ", &["New Zealand", "New Zealander"]),
+    ("NI", &["Nicaragua", "Nicaraguan"]),
+    ("NE", &["Niger", "Nigerien"]),
+    ("NG", &["Nigeria", "Nigerian"]),
+    ("MK", &["North Macedonia", "Macedonia", "Macedonian"]),
+    ("NO", &["Norway", "Norwegian"]),
+    ("OM", &["Oman", "Omani"]),
+    ("PK", &["Pakistan", "Pakistani"]),
+    ("PW", &["Palau", "Palauan"]),
+    ("PS", &["Palestine", "Palestinian"]),
+    ("PA", &["Panama", "Panamanian"]),
+    ("PG", &["Papua New Guinea", "Papua New Guinean"]),
+    ("PY", &["Paraguay", "Paraguayan"]),
+    ("PE", &["Peru", "Peruvian"]),
+    ("PH", &["Philippines", "Filipino", "Philippine"]),
+    ("PL", &["Poland", "Polish"]),
+    ("PT", &["Portugal", "Portuguese"]),
+    ("QA", &["Qatar", "Qatari"]),
+    ("RO", &["Romania", "Romanian"]),
+    ("RU", &["Russia", "Russian Federation", "Russian"]),
+    ("RW", &["Rwanda", "Rwandan"]),
+    ("KN", &["Saint Kitts and Nevis", "Kittitian", "Nevisian"]),
+    ("LC", &["Saint Lucia", "Saint Lucian"]),
+    ("VC", &["Saint Vincent and the Grenadines", "Vincentian"]),
+    ("WS", &["Samoa", "Samoan"]),
+    ("SM", &["San Marino", "Sammarinese"]),
+    ("ST", &["Sao Tome and Principe", "Santomean"]),
+    ("SA", &["Saudi Arabia", "Saudi", "Saudi Arabian"]),
+    ("SN", &["Senegal", "Senegalese"]),
+    ("RS", &["Serbia", "Serbian"]),
+    ("SC", &["Seychelles", "Seychellois"]),
+    ("SL", &["Sierra Leone", "Sierra Leonean"]),
+    ("SG", &["Singapore", "Singaporean"]),
+    ("SK", &["Slovakia", "Slovak"]),
+    ("SI", &["Slovenia", "Slovenian", "Slovene"]),
+    ("SB", &["Solomon Islands", "Solomon Islander"]),
+    ("SO", &["Somalia", "Somali"]),
+    ("ZA", &["South Africa", "South African"]),
+    ("SS", &["South Sudan", "South Sudanese"]),
+    ("ES", &["Spain", "Spanish"]),
+    ("LK", &["Sri Lanka", "Sri Lankan"]),
+    ("SD", &["Sudan", "Sudanese"]),
+    ("SR", &["Suriname", "Surinamese"]),
+    ("SE", &["Sweden", "Swedish"]),
+    ("CH", &["Switzerland", "Swiss"]),
+    ("SY", &["Syria", "Syrian Arab Republic", "Syrian"]),
+    ("TW", &["Taiwan", "Taiwanese"]),
+    ("TJ", &["Tajikistan", "Tajik"]),
+    ("TZ", &["Tanzania", "Tanzanian"]),
+    ("TH", &["Thailand", "Thai"]),
+    ("TL", &["Timor-Leste", "East Timor", "Timorese"]),
+    ("TG", &["Togo", "Togolese"]),
+    ("TO", &["Tonga", "Tongan"]),
+    ("TT", &["Trinidad and Tobago", "Trinidadian", "Tobagonian"]),
+    ("TN", &["Tunisia", "Tunisian"]),
+    ("TR", &["Turkey", "Turkiye", "Turkish"]),
+    ("TM", &["Turkmenistan", "Turkmen"]),
+    ("TV", &["Tuvalu", "Tuvaluan"]),
+    ("UG", &["Uganda", "Ugandan"]),
+    ("UA", &["Ukraine", "Ukrainian"]),
+    ("AE", &["United Arab Emirates", "UAE", "Emirati"]),
+    ("GB", &["United Kingdom", "UK", "Great Britain", "British"]),
+    ("US", &["United States", "United States of America", "USA", "American"]),
+    ("UY", &["Uruguay", "Uruguayan"]),
+    ("UZ", &["Uzbekistan", "Uzbek"]),
+    ("VU", &["Vanuatu", "Ni-Vanuatu"]),
+    ("VA", &["Vatican City", "Holy See"]),
+    ("VE", &["Venezuela", "Venezuelan"]),
+    ("VN", &["Vietnam", "Viet Nam", "Vietnamese"]),
+    ("YE", &["Yemen", "Yemeni"]),
+    ("ZM", &["Zambia", "Zambian"]),
+    ("ZW", &["Zimbabwe", "Zimbabwean"]),
+];
+
+/// Two letter code of a US state, from its code or name in any case, e.g. `il`, `Illinois` or `I.L.`
+pub fn canonical_us_state(state: &str) -> Option<&'static str> {
+    let state: String = state.chars().filter(|c| *c != '.').collect();
+    let state = state.split_whitespace().collect::<Vec<_>>().join(" ");
+    US_STATES
+        .iter()
+        .find(|(code, name)| code.eq_ignore_ascii_case(&state) || name.replace('.', "").eq_ignore_ascii_case(&state))
+        .map(|(code, _)| *code)
+}
+
+/// Two letter codes and names of US states, DC, territories and military postal regions
+const US_STATES: [(&str, &str); 59] = [
+    ("AL", "Alabama"),
+    ("AK", "Alaska"),
+    ("AZ", "Arizona"),
+    ("AR", "Arkansas"),
+    ("CA", "California"),
+    ("CO", "Colorado"),
+    ("CT", "Connecticut"),
+    ("DE", "Delaware"),
+    ("FL", "Florida"),
+    ("GA", "Georgia"),
+    ("HI", "Hawaii"),
+    ("ID", "Idaho"),
+    ("IL", "Illinois"),
+    ("IN", "Indiana"),
+    ("IA", "Iowa"),
+    ("KS", "Kansas"),
+    ("KY", "Kentucky"),
+    ("LA", "Louisiana"),
+    ("ME", "Maine"),
+    ("MD", "Maryland"),
+    ("MA", "Massachusetts"),
+    ("MI", "Michigan"),
+    ("MN", "Minnesota"),
+    ("MS", "Mississippi"),
+    ("MO", "Missouri"),
+    ("MT", "Montana"),
+    ("NE", "Nebraska"),
+    ("NV", "Nevada"),
+    ("NH", "New Hampshire"),
+    ("NJ", "New Jersey"),
+    ("NM", "New Mexico"),
+    ("NY", "New York"),
+    ("NC", "North Carolina"),
+    ("ND", "North Dakota"),
+    ("OH", "Ohio"),
+    ("OK", "Oklahoma"),
+    ("OR", "Oregon"),
+    ("PA", "Pennsylvania"),
+    ("RI", "Rhode Island"),
+    ("SC", "South Carolina"),
+    ("SD", "South Dakota"),
+    ("TN", "Tennessee"),
+    ("TX", "Texas"),
+    ("UT", "Utah"),
+    ("VT", "Vermont"),
+    ("VA", "Virginia"),
+    ("WA", "Washington"),
+    ("WV", "West Virginia"),
+    ("WI", "Wisconsin"),
+    ("WY", "Wyoming"),
+    ("DC", "District of Columbia"),
+    ("AS", "American Samoa"),
+    ("GU", "Guam"),
+    ("MP", "Northern Mariana Islands"),
+    ("PR", "Puerto Rico"),
+    ("VI", "U.S. Virgin Islands"),
+    ("AA", "Armed Forces Americas"),
+    ("AE", "Armed Forces Europe"),
+    ("AP", "Armed Forces Pacific"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn countries_are_found_by_name_or_demonym() {
+        assert_eq!(country_code_by_name("American").as_deref(), Some("US"));
+        assert_eq!(country_code_by_name("the Netherlands").as_deref(), Some("NL"));
+        assert_eq!(country_code_by_name("u.k.").as_deref(), Some("GB"));
+    }
+
+    #[test]
+    fn two_letter_values_are_taken_as_codes() {
+        assert_eq!(country_code_by_name("de").as_deref(), Some("DE"));
+        assert_eq!(country_code_by_name("Atlantean"), None);
+    }
+}
@@ -0,0 +1,179 @@
+//! Serde helpers for API timestamps
+
+/// Accepts unix seconds, unix milliseconds (as numbers, fractional ones included, or digit strings) and RFC 3339 strings.
+/// Always serializes to RFC 3339 in UTC, so timestamps outside the years 0000 to 9999 in UTC are rejected.
+///
+/// Use with `#[serde(with = "lenient")]`, or `lenient::option` for `Option<DateTime>`.
+pub mod lenient {
+    use json_api_client::types::DateTime;
+    use serde::de::{self, Deserializer, Visitor};
+    use serde::Serializer;
+    use std::fmt;
+    use time::format_description::well_known::Rfc3339;
+    use time::UtcOffset;
+
+    /// Numbers at least this large are read as milliseconds. As seconds they would be past the year 5000.
+    const MILLIS_THRESHOLD: i64 = 100_000_000_000;
+
+    pub fn serialize<S: Serializer>(datetime: &DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        time::serde::rfc3339::serialize(&datetime.to_offset(UtcOffset::UTC), serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+        deserializer.deserialize_any(LenientVisitor)
+    }
+
+    struct LenientVisitor;
+
+    impl<'de> Visitor<'de> for LenientVisitor {
+        type Value = DateTime;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a unix timestamp in seconds or milliseconds, or an RFC 3339 string")
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<DateTime, E> {
+            from_unix(value)
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<DateTime, E> {
+            let value = i64::try_from(value).map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))?;
+            from_unix(value)
+        }
+
+        fn visit_f64<E: de::Error>(self, value: f64) -> Result<DateTime, E> {
+            let nanos_per_unit = if value.abs() >= MILLIS_THRESHOLD as f64 { 1e6 } else { 1e9 };
+            let nanos = (value * nanos_per_unit).round();
+            if !nanos.is_finite() {
+                return Err(E::invalid_value(de::Unexpected::Float(value), &self));
+            }
+            DateTime::from_unix_timestamp_nanos(nanos as i128).map_err(E::custom).and_then(serializable)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<DateTime, E> {
+            if let Ok(timestamp) = value.parse::<i64>() {
+                return from_unix(timestamp);
+            }
+            DateTime::parse(value, &Rfc3339)
+                .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+                .and_then(serializable)
+        }
+    }
+
+    fn from_unix<E: de::Error>(timestamp: i64) -> Result<DateTime, E> {
+        let datetime = if timestamp.abs() >= MILLIS_THRESHOLD {
+            DateTime::from_unix_timestamp_nanos(timestamp as i128 * 1_000_000)
+        } else {
+            DateTime::from_unix_timestamp(timestamp)
+        };
+        datetime.map_err(E::custom).and_then(serializable)
+    }
+
+    /// Unix seconds of 0000-01-01T00:00:00Z and 9999-12-31T23:59:59Z
+    const RFC3339_RANGE: std::ops::RangeInclusive<i64> = -62_167_219_200..=253_402_300_799;
+
+    /// RFC 3339 has four digit years, `serialize` would fail on anything outside them in UTC
+    fn serializable<E: de::Error>(datetime: DateTime) -> Result<DateTime, E> {
+        if !RFC3339_RANGE.contains(&datetime.unix_timestamp()) {
+            return Err(E::custom("timestamp is outside the years 0000 to 9999 in UTC"));
+        }
+        Ok(datetime)
+    }
+
+    pub mod option {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(datetime: &Option<DateTime>, serializer: S) -> Result<S::Ok, S::Error> {
+            time::serde::rfc3339::option::serialize(&datetime.map(|d| d.to_offset(UtcOffset::UTC)), serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime>, D::Error> {
+            deserializer.deserialize_option(OptionVisitor)
+        }
+
+        struct OptionVisitor;
+
+        impl<'de> Visitor<'de> for OptionVisitor {
+            type Value = Option<DateTime>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("null, a unix timestamp or an RFC 3339 string")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Option<DateTime>, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Option<DateTime>, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Option<DateTime>, D::Error> {
+                super::deserialize(deserializer).map(Some)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use json_api_client::types::DateTime;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use time::macros::datetime;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Timestamps {
+        #[serde(with = "super::lenient")]
+        at: DateTime,
+        #[serde(with = "super::lenient::option")]
+        until: Option<DateTime>,
+    }
+
+    #[test]
+    fn accepts_seconds_millis_and_rfc3339() {
+        let expected = datetime!(2023-01-10 12:00 UTC);
+        for value in [
+            json!(1673352000),
+            json!(1673352000000_i64),
+            json!("1673352000"),
+            json!("2023-01-10T12:00:00Z"),
+            json!("2023-01-10T07:00:00-05:00"),
+        ] {
+            let parsed: Timestamps = serde_json::from_value(json!({ "at": value.clone(), "until": value })).unwrap();
+            assert_eq!(parsed.at, expected);
+            assert_eq!(parsed.until, Some(expected));
+        }
+
+        let parsed: Timestamps = serde_json::from_value(json!({ "at": 0, "until": null })).unwrap();
+        assert_eq!(parsed.until, None);
+        assert!(serde_json::from_value::<Timestamps>(json!({ "at": "yesterday", "until": null })).is_err());
+    }
+
+    #[test]
+    fn accepts_fractional_timestamps() {
+        let parsed: Timestamps = serde_json::from_value(json!({ "at": 1673352000.5, "until": 1673352000500.0 })).unwrap();
+
+        assert_eq!(parsed.at, datetime!(2023-01-10 12:00:00.5 UTC));
+        assert_eq!(parsed.until, Some(datetime!(2023-01-10 12:00:00.5 UTC)));
+    }
+
+    #[test]
+    fn rejects_years_rfc3339_cant_represent() {
+        // 10000-01-01T00:00:00Z in milliseconds, one second before 0000-01-01T00:00:00Z, and 10000-01-01T04:00:00Z
+        for value in [json!(253_402_300_800_000_i64), json!(-62167219201_i64), json!("9999-12-31T23:00:00-05:00")] {
+            assert!(serde_json::from_value::<Timestamps>(json!({ "at": value, "until": null })).is_err());
+        }
+        assert!(serde_json::from_value::<Timestamps>(json!({ "at": "9999-12-31T23:59:59Z", "until": null })).is_ok());
+    }
+
+    #[test]
+    fn serializes_as_rfc3339_in_utc() {
+        let timestamps = Timestamps {
+            at: datetime!(2023-01-10 07:00 -5),
+            until: None,
+        };
+
+        assert_eq!(serde_json::to_value(&timestamps).unwrap(), json!({ "at": "2023-01-10T12:00:00Z", "until": null }));
+    }
+}
@@ -0,0 +1,370 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use time::Duration;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::types::*;
+use crate::{Error, ErrorKind, Result};
+use json_api_client::types::*;
+
+/// Downloadable file attached to an API response
+pub trait Document {
+    fn download_url(&self) -> &str;
+    /// Number of seconds the download URL is valid for, counted from `fetched_at`
+    fn download_url_expires(&self) -> u64;
+    /// When the document was fetched from the API, if known
+    fn fetched_at(&self) -> Option<DateTime>;
+    /// Label used for the document type in file names and manifests
+    fn type_label(&self) -> String;
+
+    /// Absolute expiry of the download URL. None if the fetch time is unknown.
+    fn download_url_expires_at(&self) -> Option<DateTime> {
+        let fetched_at = self.fetched_at()?;
+        let expires = i64::try_from(self.download_url_expires()).unwrap_or(i64::MAX);
+        fetched_at.checked_add(Duration::seconds(expires))
+    }
+
+    /// True if the URL is known to have expired at `now`
+    fn is_download_url_expired(&self, now: DateTime) -> bool {
+        self.download_url_expires_at().map(|expires_at| expires_at <= now).unwrap_or(false)
+    }
+}
+
+impl Document for AccreditationDocument {
+    fn download_url(&self) -> &str {
+        self.download_url.expose()
+    }
+
+    fn download_url_expires(&self) -> u64 {
+        self.download_url_expires
+    }
+
+    fn fetched_at(&self) -> Option<DateTime> {
+        self.fetched_at
+    }
+
+    fn type_label(&self) -> String {
+        match &self.document_type {
+            DocumentType::CertificationLetter => "certification-letter".to_string(),
+            DocumentType::Unknown(document_type) => unknown_type_label(document_type),
+        }
+    }
+}
+
+impl Document for IdentityDocument {
+    fn download_url(&self) -> &str {
+        self.download_url.expose()
+    }
+
+    fn download_url_expires(&self) -> u64 {
+        self.download_url_expires
+    }
+
+    fn fetched_at(&self) -> Option<DateTime> {
+        self.fetched_at
+    }
+
+    fn type_label(&self) -> String {
+        match &self.document_type {
+            IdentityDocumentType::DriversLicense => "drivers-license".to_string(),
+            IdentityDocumentType::StateIdCard => "state-id-card".to_string(),
+            IdentityDocumentType::Passport => "passport".to_string(),
+            IdentityDocumentType::Unknown(document_type) => unknown_type_label(document_type),
+        }
+    }
+}
+
+/// Unknown types end up in file names, so anything but ASCII letters and digits is replaced
+fn unknown_type_label(document_type: &str) -> String {
+    document_type
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect()
+}
+
+/// Identifies a document by the API resource it belongs to, so it can be fetched again when its URL expires
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum DocumentLocator {
+    /// `identity_files[index]` of the subject's identity
+    Identity { index: usize },
+    /// `identity_files[index]` of a beneficial owner or control person
+    DependencyIdentity { dependency_id: EntityId, index: usize },
+    /// `documents[index]` of the accreditation with the given ID
+    Accreditation { accreditation_id: String, index: usize },
+}
+
+/// Default limit for `Client::with_max_document_size`
+pub const DEFAULT_MAX_DOCUMENT_SIZE: u64 = 25 * 1024 * 1024;
+
+/// Number of leading bytes needed to detect the media type
+pub(crate) const SNIFF_LENGTH: usize = 12;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    #[serde(rename = "application/pdf")]
+    Pdf,
+    #[serde(rename = "image/jpeg")]
+    Jpeg,
+    #[serde(rename = "image/png")]
+    Png,
+    #[serde(rename = "image/heic")]
+    Heic,
+}
+
+impl MediaType {
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            MediaType::Pdf => "application/pdf",
+            MediaType::Jpeg => "image/jpeg",
+            MediaType::Png => "image/png",
+            MediaType::Heic => "image/heic",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            MediaType::Pdf => "pdf",
+            MediaType::Jpeg => "jpg",
+            MediaType::Png => "png",
+            MediaType::Heic => "heic",
+        }
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mime_type())
+    }
+}
+
+/// Reason a downloaded body was not accepted as a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnexpectedContent {
+    Empty,
+    /// HTML or XML, typically an error page
+    Markup,
+    /// JSON, typically an API error
+    Json,
+    /// None of the supported document formats
+    Unrecognized,
+}
+
+impl fmt::Display for UnexpectedContent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            UnexpectedContent::Empty => "empty body",
+            UnexpectedContent::Markup => "HTML/XML body",
+            UnexpectedContent::Json => "JSON body",
+            UnexpectedContent::Unrecognized => "unrecognized content",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Detects the media type of a document from its first bytes (at least 12 are needed to detect HEIC)
+pub fn sniff_media_type(content: &[u8]) -> std::result::Result<MediaType, UnexpectedContent> {
+    const HEIF_BRANDS: [&[u8]; 8] = [b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1"];
+
+    if content.starts_with(b"%PDF-") {
+        return Ok(MediaType::Pdf);
+    }
+    if content.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        return Ok(MediaType::Jpeg);
+    }
+    if content.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        return Ok(MediaType::Png);
+    }
+    if content.len() >= 12 && &content[4..8] == b"ftyp" && HEIF_BRANDS.contains(&&content[8..12]) {
+        return Ok(MediaType::Heic);
+    }
+
+    let text = content.strip_prefix(b"\xEF\xBB\xBF".as_slice()).unwrap_or(content);
+    match text.iter().find(|b| !b.is_ascii_whitespace()) {
+        None => Err(UnexpectedContent::Empty),
+        Some(b'<') => Err(UnexpectedContent::Markup),
+        Some(b'{') | Some(b'[') => Err(UnexpectedContent::Json),
+        Some(_) => Err(UnexpectedContent::Unrecognized),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocumentDownload {
+    /// Number of bytes written
+    pub size: u64,
+    /// Hex encoded SHA-256 of the content
+    pub sha256: String,
+    /// Media type detected from the content
+    pub media_type: MediaType,
+}
+
+/// Document response whose media type was detected from its first bytes, the rest of the content still unread
+pub(crate) struct DocumentBody {
+    response: reqwest::Response,
+    /// Content read to detect the media type, not written yet
+    head: Vec<u8>,
+    media_type: MediaType,
+    content_length: Option<u64>,
+    size: u64,
+    max_size: u64,
+    hasher: Sha256,
+}
+
+impl DocumentBody {
+    /// Fails with `UnexpectedDocumentContent` if the body is not a PDF, JPEG, PNG or HEIC file
+    pub(crate) async fn open(mut response: reqwest::Response, max_size: u64) -> Result<DocumentBody> {
+        // Read before the body, which counts the length down as it is consumed
+        let content_length = response.content_length();
+        if content_length.map(|length| length > max_size).unwrap_or(false) {
+            return Err(Error::ApiError(ErrorKind::DocumentTooLarge(max_size)));
+        }
+
+        let mut head = Vec::with_capacity(SNIFF_LENGTH);
+        while head.len() < SNIFF_LENGTH {
+            match response.chunk().await? {
+                Some(chunk) => head.extend_from_slice(&chunk),
+                None => break,
+            }
+            if head.len() as u64 > max_size {
+                return Err(Error::ApiError(ErrorKind::DocumentTooLarge(max_size)));
+            }
+        }
+        let media_type = sniff_media_type(&head).map_err(|e| Error::ApiError(ErrorKind::UnexpectedDocumentContent(e)))?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&head);
+        Ok(DocumentBody {
+            response,
+            size: head.len() as u64,
+            head,
+            media_type,
+            content_length,
+            max_size,
+            hasher,
+        })
+    }
+
+    pub(crate) fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+
+    /// Size announced in the `Content-Length` header, if any
+    pub(crate) fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    /// Streams the content into `writer`. `DocumentTooLarge` may be returned after part of it has been written.
+    pub(crate) async fn copy_to<W: AsyncWrite + Unpin>(mut self, writer: &mut W) -> Result<DocumentDownload> {
+        writer.write_all(&self.head).await?;
+        while let Some(chunk) = self.response.chunk().await? {
+            self.size += chunk.len() as u64;
+            if self.size > self.max_size {
+                return Err(Error::ApiError(ErrorKind::DocumentTooLarge(self.max_size)));
+            }
+            self.hasher.update(&chunk);
+            writer.write_all(&chunk).await?;
+        }
+        writer.flush().await?;
+
+        Ok(DocumentDownload {
+            size: self.size,
+            sha256: format!("{:x}", self.hasher.finalize()),
+            media_type: self.media_type,
+        })
+    }
+}
+
+/// Records the fetch time on a response and all documents in it
+pub(crate) trait FetchStamp {
+    fn stamp(&mut self, fetched_at: DateTime);
+}
+
+impl FetchStamp for ProfileResponse {
+    fn stamp(&mut self, fetched_at: DateTime) {
+        self.fetched_at = Some(fetched_at);
+    }
+}
+
+impl FetchStamp for AccreditationsResponse {
+    fn stamp(&mut self, fetched_at: DateTime) {
+        self.fetched_at = Some(fetched_at);
+        let documents: Box<dyn Iterator<Item = &mut AccreditationDocument>> = match &mut self.accreditations {
+            Accreditations::Individual(accreditations) => Box::new(accreditations.iter_mut().flat_map(|a| a.documents.iter_mut())),
+            Accreditations::Business(accreditations) => Box::new(accreditations.iter_mut().flat_map(|a| a.documents.iter_mut())),
+        };
+        for document in documents {
+            document.fetched_at = Some(fetched_at);
+        }
+    }
+}
+
+impl FetchStamp for IdentityResponse {
+    fn stamp(&mut self, fetched_at: DateTime) {
+        self.fetched_at = Some(fetched_at);
+        stamp_identity_files(&mut self.identity_details, fetched_at);
+    }
+}
+
+impl FetchStamp for DependencyIdentityResponse {
+    fn stamp(&mut self, fetched_at: DateTime) {
+        self.fetched_at = Some(fetched_at);
+        stamp_identity_files(&mut self.identity_details, fetched_at);
+    }
+}
+
+fn stamp_identity_files(details: &mut IdentityDetails, fetched_at: DateTime) {
+    let files = match details {
+        IdentityDetails::Individual(individual) => &mut individual.identity_files,
+        IdentityDetails::Business(business) => &mut business.identity_files,
+    };
+    for file in files {
+        file.fetched_at = Some(fetched_at);
+    }
+}
+
+/// Documents of the accreditation with the given ID
+pub(crate) fn accreditation_documents<'a>(accreditations: &'a AccreditationsResponse, accreditation_id: &str) -> Option<&'a [AccreditationDocument]> {
+    match &accreditations.accreditations {
+        Accreditations::Individual(individual) => individual.iter().find(|a| a.id == accreditation_id).map(|a| a.documents.as_slice()),
+        Accreditations::Business(business) => business.iter().find(|a| a.id == accreditation_id).map(|a| a.documents.as_slice()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    #[test]
+    fn expiry_counts_from_fetch_time() {
+        let mut document: IdentityDocument = serde_json::from_value(json!({
+            "download_url": "https://example.com/passport.jpg",
+            "download_url_expires": 300,
+            "type": "passport",
+        }))
+        .unwrap();
+        assert_eq!(document.download_url_expires_at(), None);
+        assert!(!document.is_download_url_expired(datetime!(2023-01-10 12:00 UTC)));
+
+        document.fetched_at = Some(datetime!(2023-01-10 12:00 UTC));
+        assert_eq!(document.download_url_expires_at(), Some(datetime!(2023-01-10 12:05 UTC)));
+        assert!(!document.is_download_url_expired(datetime!(2023-01-10 12:04:59 UTC)));
+        assert!(document.is_download_url_expired(datetime!(2023-01-10 12:05 UTC)));
+    }
+
+    #[test]
+    fn sniffs_documents_and_rejects_error_pages() {
+        assert_eq!(sniff_media_type(b"%PDF-1.7\n%"), Ok(MediaType::Pdf));
+        assert_eq!(sniff_media_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0, 0x10]), Ok(MediaType::Jpeg));
+        assert_eq!(sniff_media_type(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Ok(MediaType::Png));
+        assert_eq!(sniff_media_type(b"\0\0\0\x18ftypheic\0\0\0\0"), Ok(MediaType::Heic));
+
+        assert_eq!(sniff_media_type(b""), Err(UnexpectedContent::Empty));
+        assert_eq!(sniff_media_type(b"\n  <!DOCTYPE html><html>"), Err(UnexpectedContent::Markup));
+        assert_eq!(sniff_media_type(b"<?xml version=\"1.0\"?><Error>"), Err(UnexpectedContent::Markup));
+        assert_eq!(sniff_media_type(b"{\"error\": \"not found\"}"), Err(UnexpectedContent::Json));
+        assert_eq!(sniff_media_type(b"GIF89a\x01\0\x01\0"), Err(UnexpectedContent::Unrecognized));
+    }
+}
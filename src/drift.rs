@@ -0,0 +1,109 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Differences between the JSON sent by the API and the structs of this crate
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaDrift {
+    /// Endpoint with IDs as placeholders, e.g. `identity` or `identity/{id}`
+    pub endpoint: String,
+    /// Fields present in the JSON but absent from the structs, e.g. `identity_details.risk_monitor_matches[].source`
+    pub unknown_fields: Vec<String>,
+    /// Fields the structs expect that were not present in the JSON
+    pub missing_fields: Vec<String>,
+}
+
+pub type SchemaDriftHandler = Arc<dyn Fn(&SchemaDrift) + Send + Sync>;
+
+/// Compares the field names of `raw` (as received) with `typed` (the parsed response serialized back).
+/// Returns None if they match.
+pub fn detect_schema_drift(endpoint: &str, raw: &Value, typed: &Value) -> Option<SchemaDrift> {
+    let mut unknown_fields = BTreeSet::new();
+    let mut missing_fields = BTreeSet::new();
+    compare(raw, typed, "", &mut unknown_fields, &mut missing_fields);
+
+    if unknown_fields.is_empty() && missing_fields.is_empty() {
+        return None;
+    }
+    Some(SchemaDrift {
+        endpoint: endpoint.to_string(),
+        unknown_fields: unknown_fields.into_iter().collect(),
+        missing_fields: missing_fields.into_iter().collect(),
+    })
+}
+
+fn compare(raw: &Value, typed: &Value, path: &str, unknown_fields: &mut BTreeSet<String>, missing_fields: &mut BTreeSet<String>) {
+    match (raw, typed) {
+        (Value::Object(raw), Value::Object(typed)) => {
+            for (key, raw_value) in raw {
+                let field = join(path, key);
+                match typed.get(key) {
+                    Some(typed_value) => compare(raw_value, typed_value, &field, unknown_fields, missing_fields),
+                    None => {
+                        unknown_fields.insert(field);
+                    },
+                }
+            }
+            for key in typed.keys().filter(|key| !raw.contains_key(*key)) {
+                missing_fields.insert(join(path, key));
+            }
+        },
+        (Value::Array(raw), Value::Array(typed)) => {
+            let field = format!("{}[]", path);
+            for (raw_value, typed_value) in raw.iter().zip(typed) {
+                compare(raw_value, typed_value, &field, unknown_fields, missing_fields);
+            }
+        },
+        // Scalars (or values with a custom representation) have no fields to compare
+        _ => {},
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::types::*;
+    use serde_json::json;
+
+    #[test]
+    fn reports_unknown_and_missing_fields() {
+        let raw = json!({
+            "id": "subject",
+            "matches": [{ "score": 10, "source": "ofac" }, { "score": 20, "source": "un" }],
+            "new_field": true,
+        });
+        let typed = json!({
+            "id": "subject",
+            "matches": [{ "score": 10, "name": null }, { "score": 20, "name": null }],
+            "access_expires_at": null,
+        });
+
+        let drift = detect_schema_drift("identity", &raw, &typed).unwrap();
+
+        assert_eq!(drift.endpoint, "identity");
+        assert_eq!(drift.unknown_fields, vec!["matches[].source", "new_field"]);
+        assert_eq!(drift.missing_fields, vec!["access_expires_at", "matches[].name"]);
+        assert_eq!(detect_schema_drift("identity", &typed, &typed), None);
+    }
+
+    #[test]
+    fn parsed_responses_round_trip_without_drift() {
+        let raw = dependency_identity(
+            "holding",
+            "business",
+            business_identity("Holding LLC", vec![individual_owner("alice", "Alice", "Smith", 100)], vec![control_person("carol", "Carol", "White", "CEO")]),
+        );
+        let response: DependencyIdentityResponse = serde_json::from_value(raw.clone()).unwrap();
+
+        assert_eq!(detect_schema_drift("identity/{id}", &raw, &serde_json::to_value(&response).unwrap()), None);
+    }
+}
@@ -0,0 +1,257 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::clock::Clock;
+use crate::config::{self, CodeList};
+use crate::countries::*;
+use crate::types::*;
+use crate::Result;
+use json_api_client::types::*;
+
+/// Age in full years on `today`. Birthdays on February 29 are reached on March 1 in other years.
+pub fn age_on(birth_date: Date, today: Date) -> u32 {
+    let years = today.year() - birth_date.year();
+    let had_birthday = (today.month() as u8, today.day()) >= (birth_date.month() as u8, birth_date.day());
+    let age = if had_birthday { years } else { years - 1 };
+    age.max(0) as u32
+}
+
+/// Rules gating a feature on age and residency. Country lists hold codes as sent by the API, e.g. `US`.
+/// Empty allow lists allow everything. In TOML:
+///
+/// ```toml
+/// min_age = 18
+/// blocked_residence_countries = ["IR", "KP"]
+/// blocked_us_states = ["NY"]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct EligibilityRules {
+    pub min_age: Option<u32>,
+    pub allowed_residence_countries: CodeList,
+    pub blocked_residence_countries: CodeList,
+    pub allowed_citizenship_countries: CodeList,
+    pub blocked_citizenship_countries: CodeList,
+    /// Two letter codes, only checked for residents of the US. States given by name in the address are matched too.
+    pub allowed_us_states: CodeList,
+    pub blocked_us_states: CodeList,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum IneligibilityReason {
+    TooYoung {
+        age: u32,
+        min_age: u32,
+    },
+    ResidenceCountryNotAllowed {
+        country: String,
+    },
+    ResidenceCountryBlocked {
+        country: String,
+    },
+    CitizenshipNotAllowed {
+        country: String,
+    },
+    CitizenshipBlocked {
+        country: String,
+    },
+    /// The country of `field` isn't known to this version of the crate, so it can't be checked
+    CountryUnknown {
+        field: String,
+    },
+    /// The residence is in the US, but has no state while US states are restricted
+    UsStateMissing,
+    /// The state of a US residence isn't a known state code or name, e.g. it is misspelled
+    UsStateUnknown {
+        state: String,
+    },
+    UsStateNotAllowed {
+        state: String,
+    },
+    UsStateBlocked {
+        state: String,
+    },
+}
+
+impl fmt::Display for IneligibilityReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IneligibilityReason::TooYoung { age, min_age } => write!(f, "age {} is below the minimum of {}", age, min_age),
+            IneligibilityReason::ResidenceCountryNotAllowed { country } => write!(f, "residence country {} is not allowed", country),
+            IneligibilityReason::ResidenceCountryBlocked { country } => write!(f, "residence country {} is blocked", country),
+            IneligibilityReason::CitizenshipNotAllowed { country } => write!(f, "citizenship {} is not allowed", country),
+            IneligibilityReason::CitizenshipBlocked { country } => write!(f, "citizenship {} is blocked", country),
+            IneligibilityReason::CountryUnknown { field } => write!(f, "country of {} is unknown", field),
+            IneligibilityReason::UsStateMissing => write!(f, "US residence without a state"),
+            IneligibilityReason::UsStateUnknown { state } => write!(f, "US state '{}' is unknown", state),
+            IneligibilityReason::UsStateNotAllowed { state } => write!(f, "US state {} is not allowed", state),
+            IneligibilityReason::UsStateBlocked { state } => write!(f, "US state {} is blocked", state),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EligibilityReport {
+    pub age: u32,
+    pub reasons: Vec<IneligibilityReason>,
+}
+
+impl EligibilityReport {
+    pub fn passed(&self) -> bool {
+        self.reasons.is_empty()
+    }
+}
+
+impl EligibilityRules {
+    pub fn from_toml(rules: &str) -> Result<EligibilityRules> {
+        config::from_toml("eligibility rules", rules)
+    }
+
+    pub fn from_json(rules: &str) -> Result<EligibilityRules> {
+        config::from_json("eligibility rules", rules)
+    }
+
+    pub fn evaluate(&self, details: &IndividualIdentityDetails, clock: &impl Clock) -> EligibilityReport {
+        let age = age_on(*details.birth_date.expose(), clock.today());
+        let mut reasons = Vec::new();
+
+        if let Some(min_age) = self.min_age {
+            if age < min_age {
+                reasons.push(IneligibilityReason::TooYoung { age, min_age });
+            }
+        }
+
+        let residence = country_code(&details.residence_location.country);
+        match &residence {
+            None => reasons.push(IneligibilityReason::CountryUnknown {
+                field: "residence_location.country".to_string(),
+            }),
+            Some(residence) => {
+                if !self.allowed_residence_countries.allows(residence) {
+                    reasons.push(IneligibilityReason::ResidenceCountryNotAllowed { country: residence.clone() });
+                }
+                if self.blocked_residence_countries.contains(residence) {
+                    reasons.push(IneligibilityReason::ResidenceCountryBlocked { country: residence.clone() });
+                }
+            },
+        }
+
+        match country_code(&details.citizenship_country) {
+            None => reasons.push(IneligibilityReason::CountryUnknown {
+                field: "citizenship_country".to_string(),
+            }),
+            Some(citizenship) => {
+                if !self.allowed_citizenship_countries.allows(&citizenship) {
+                    reasons.push(IneligibilityReason::CitizenshipNotAllowed { country: citizenship.clone() });
+                }
+                if self.blocked_citizenship_countries.contains(&citizenship) {
+                    reasons.push(IneligibilityReason::CitizenshipBlocked { country: citizenship });
+                }
+            },
+        }
+
+        if residence.as_deref() == Some("US") && (!self.allowed_us_states.is_empty() || !self.blocked_us_states.is_empty()) {
+            // The state may be given in `region` instead, and by name
+            let location = &details.residence_location;
+            let state = [&location.state, &location.region]
+                .into_iter()
+                .flatten()
+                .map(|state| state.trim())
+                .find(|state| !state.is_empty());
+            match state.map(|state| (state, canonical_us_state(state))) {
+                None => reasons.push(IneligibilityReason::UsStateMissing),
+                Some((state, None)) => reasons.push(IneligibilityReason::UsStateUnknown { state: state.to_string() }),
+                Some((_, Some(state))) => {
+                    if !self.allowed_us_states.allows(state) {
+                        reasons.push(IneligibilityReason::UsStateNotAllowed { state: state.to_string() });
+                    }
+                    if self.blocked_us_states.contains(state) {
+                        reasons.push(IneligibilityReason::UsStateBlocked { state: state.to_string() });
+                    }
+                },
+            }
+        }
+
+        EligibilityReport { age, reasons }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::fixtures::*;
+    use serde_json::json;
+    use time::macros::{date, datetime};
+
+    #[test]
+    fn age_counts_full_years() {
+        assert_eq!(age_on(date!(1980 - 05 - 17), date!(2023 - 05 - 16)), 42);
+        assert_eq!(age_on(date!(1980 - 05 - 17), date!(2023 - 05 - 17)), 43);
+        assert_eq!(age_on(date!(2004 - 02 - 29), date!(2022 - 02 - 28)), 17);
+        assert_eq!(age_on(date!(2004 - 02 - 29), date!(2022 - 03 - 01)), 18);
+    }
+
+    fn evaluate(rules: &str, overrides: serde_json::Value) -> EligibilityReport {
+        let rules = EligibilityRules::from_toml(rules).unwrap();
+        let details: IndividualIdentityDetails = serde_json::from_value(with(individual_identity("Alice", "Smith"), overrides)).unwrap();
+        rules.evaluate(&details, &FixedClock(datetime!(2023-01-10 12:00 UTC)))
+    }
+
+    #[test]
+    fn eligible_subjects_have_no_reasons() {
+        let report = evaluate("min_age = 18\nblocked_citizenship_countries = [\"IR\"]\nblocked_us_states = [\"NY\"]", json!({}));
+
+        assert_eq!(report.age, 42);
+        assert!(report.reasons.is_empty());
+    }
+
+    #[test]
+    fn subjects_under_the_minimum_age_are_too_young() {
+        let report = evaluate("min_age = 21", json!({ "birth_date": "2005-01-11" }));
+
+        assert_eq!(report.age, 17);
+        assert_eq!(report.reasons, vec![IneligibilityReason::TooYoung { age: 17, min_age: 21 }]);
+    }
+
+    #[test]
+    fn blocked_citizenships_are_reported() {
+        let report = evaluate("blocked_citizenship_countries = [\"IR\"]", json!({ "citizenship_country": "IR" }));
+
+        assert_eq!(report.reasons, vec![IneligibilityReason::CitizenshipBlocked { country: "IR".to_string() }]);
+    }
+
+    #[test]
+    fn blocked_us_states_are_matched_case_insensitively() {
+        let report = evaluate("blocked_us_states = [\"il\"]", json!({}));
+
+        assert_eq!(report.reasons, vec![IneligibilityReason::UsStateBlocked { state: "IL".to_string() }]);
+    }
+
+    #[test]
+    fn us_state_names_in_the_region_are_recognized() {
+        let report = evaluate("blocked_us_states = [\"IL\"]", json!({ "residence_location": { "state": null, "region": "Illinois" } }));
+
+        assert_eq!(report.reasons, vec![IneligibilityReason::UsStateBlocked { state: "IL".to_string() }]);
+    }
+
+    #[test]
+    fn missing_us_states_are_reported_when_states_are_blocked() {
+        let report = evaluate("blocked_us_states = [\"IL\"]", json!({ "residence_location": { "state": null } }));
+
+        assert_eq!(report.reasons, vec![IneligibilityReason::UsStateMissing]);
+    }
+
+    #[test]
+    fn misspelled_us_states_are_reported_unknown() {
+        let report = evaluate("blocked_us_states = [\"IL\"]", json!({ "residence_location": { "state": "Illinios" } }));
+
+        assert_eq!(report.reasons, vec![IneligibilityReason::UsStateUnknown { state: "Illinios".to_string() }]);
+    }
+
+    #[test]
+    fn unknown_rule_fields_are_rejected() {
+        assert!(EligibilityRules::from_json(r#"{ "min_agee": 18 }"#).is_err());
+    }
+}
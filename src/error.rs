@@ -1,4 +1,5 @@
-use crate::Scope;
+use crate::{DocumentLocator, RevokeType, Scope, Secret, UnexpectedContent};
+use json_api_client::types::DateTime;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -8,6 +9,27 @@ pub enum Error {
 
     #[error(transparent)]
     ClientError(#[from] json_api_client::error::Error),
+
+    /// `raw` is the response body that failed to parse, `path` the location of the failure in it (`.` for the root).
+    /// `source` is left out of the message, as it can quote the rejected value.
+    #[error("Failed to parse response at `{path}`")]
+    ParseError {
+        path: String,
+        source: serde_json::Error,
+        raw: Box<Secret<serde_json::Value>>,
+    },
+
+    #[error(transparent)]
+    DownloadError(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(String),
+
+    #[error("Invalid configuration: {0}")]
+    ConfigError(String),
 }
 
 // TODO KYC-136 add more variants for expected API errors
@@ -15,6 +37,22 @@ pub enum Error {
 pub enum ErrorKind {
     #[error("Scope not enabled: '{0}'")]
     ScopeNotEnabled(Scope),
+
+    #[error("Document download URL expired at {0}")]
+    DocumentUrlExpired(DateTime),
+
+    #[error("Document not found: {0:?}")]
+    DocumentNotFound(DocumentLocator),
+
+    #[error("Document is larger than {0} bytes")]
+    DocumentTooLarge(u64),
+
+    #[error("Document download returned {0}")]
+    UnexpectedDocumentContent(UnexpectedContent),
+
+    /// Access to the subject's data is known to be revoked, the request was not sent
+    #[error("Access to the subject's data was revoked")]
+    AccessRevoked { at: Option<DateTime>, by: Option<RevokeType> },
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
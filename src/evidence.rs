@@ -0,0 +1,537 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{self, Write};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::clock::Clock;
+use crate::document::*;
+use crate::json_file::to_json_vec;
+use crate::raw::*;
+use crate::types::*;
+use crate::{Client, Error, Result};
+use json_api_client::types::*;
+
+/// Version of the bundle manifest, bumped on incompatible changes
+pub const EVIDENCE_MANIFEST_FORMAT_VERSION: u32 = 1;
+
+/// Everything fetched about a subject at one point in time, with the JSON as received
+#[derive(Debug, Default)]
+pub struct SubjectSnapshot {
+    pub profile: Option<WithRaw<ProfileResponse>>,
+    pub accreditations: Option<WithRaw<AccreditationsResponse>>,
+    pub identity: Option<WithRaw<IdentityResponse>>,
+    /// Identities of the beneficial owners and control persons of a business subject
+    pub dependencies: Vec<WithRaw<DependencyIdentityResponse>>,
+}
+
+impl SubjectSnapshot {
+    pub fn subject_id(&self) -> Option<&EntityId> {
+        self.profile
+            .as_ref()
+            .map(|p| &p.value.id)
+            .or_else(|| self.identity.as_ref().map(|i| &i.value.id))
+            .or_else(|| self.accreditations.as_ref().map(|a| &a.value.id))
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EvidenceBundleOptions {
+    /// age X25519 public keys (`age1...`). If not empty the whole bundle is encrypted to these recipients.
+    pub encrypt_to: Vec<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestEntryKind {
+    Response,
+    Document,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    /// Path of the file inside the bundle
+    pub path: String,
+    pub kind: ManifestEntryKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locator: Option<DocumentLocator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<MediaType>,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the file
+    pub sha256: String,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub fetched_at: Option<DateTime>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct EvidenceManifest {
+    pub format_version: u32,
+    pub subject_id: Option<EntityId>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: DateTime,
+    pub encrypted: bool,
+    pub entries: Vec<ManifestEntry>,
+}
+
+struct BundleFile {
+    entry: ManifestEntry,
+    content: Vec<u8>,
+}
+
+impl Client {
+    /// Fetches every response available with the enabled scopes, plus the identities of a business' owners and control persons
+    pub async fn fetch_snapshot(&self, token: &str) -> Result<SubjectSnapshot> {
+        let mut snapshot = SubjectSnapshot::default();
+        if self.scopes.contains(&Scope::Profile) {
+            snapshot.profile = Some(self.get_profile_with_raw(token).await?);
+        }
+        if self.scopes.contains(&Scope::AccreditationStatus) {
+            snapshot.accreditations = Some(self.get_accreditations_with_raw(token).await?);
+        }
+        if self.scopes.contains(&Scope::Identity) {
+            let identity = self.get_identity_with_raw(token).await?;
+            if let IdentityDetails::Business(business) = &identity.value.identity_details {
+                let owner_ids = business.direct_beneficial_owners.iter().filter_map(|o| o.id.as_ref());
+                let person_ids = business.control_persons.iter().filter_map(|p| p.id.as_ref());
+                let mut ids: Vec<&String> = owner_ids.chain(person_ids).collect();
+                ids.sort();
+                ids.dedup();
+                for id in ids {
+                    snapshot.dependencies.push(self.get_dependency_identity_with_raw(id, token).await?);
+                }
+            }
+            snapshot.identity = Some(identity);
+        }
+        Ok(snapshot)
+    }
+
+    /// Downloads every document of the snapshot and writes them, the responses as received and a manifest into a gzipped tar archive.
+    /// Expired download URLs are refreshed using `token`. Documents are streamed into the archive as they download and the output
+    /// is passed on to `writer` as it is produced. Only a document sent without a `Content-Length` is held in memory, as the
+    /// tar header needs its size. `manifest.json` is the last entry of the archive.
+    pub async fn export_evidence_bundle<W: AsyncWrite + Unpin>(
+        &self,
+        snapshot: &SubjectSnapshot,
+        token: &str,
+        options: &EvidenceBundleOptions,
+        writer: &mut W,
+    ) -> Result<EvidenceManifest> {
+        let created_at = self.clock.now();
+        let mut bundle = BundleWriter::new(&options.encrypt_to, created_at)?;
+        let mut entries = Vec::new();
+
+        if let Some(profile) = &snapshot.profile {
+            let file = response_file("responses/profile.json", profile.raw.expose(), profile.value.fetched_at)?;
+            entries.push(bundle.append_file(file, writer).await?);
+        }
+        if let Some(WithRaw { value: accreditations, raw }) = &snapshot.accreditations {
+            let file = response_file("responses/accreditations.json", raw.expose(), accreditations.fetched_at)?;
+            entries.push(bundle.append_file(file, writer).await?);
+            let documents: Vec<(&String, &Vec<AccreditationDocument>)> = match &accreditations.accreditations {
+                Accreditations::Individual(individual) => individual.iter().map(|a| (&a.id, &a.documents)).collect(),
+                Accreditations::Business(business) => business.iter().map(|a| (&a.id, &a.documents)).collect(),
+            };
+            for (accreditation_id, documents) in documents {
+                for (index, document) in documents.iter().enumerate() {
+                    let locator = DocumentLocator::Accreditation {
+                        accreditation_id: accreditation_id.clone(),
+                        index,
+                    };
+                    let path = format!("documents/accreditations/{}/{}-{}", path_component(accreditation_id), index, document.type_label());
+                    entries.push(self.append_document(&mut bundle, path, document, locator, token, writer).await?);
+                }
+            }
+        }
+        if let Some(WithRaw { value: identity, raw }) = &snapshot.identity {
+            let file = response_file("responses/identity.json", raw.expose(), identity.fetched_at)?;
+            entries.push(bundle.append_file(file, writer).await?);
+            for (index, document) in identity.identity_details.identity_files().iter().enumerate() {
+                let path = format!("documents/identity/{}-{}", index, document.type_label());
+                let locator = DocumentLocator::Identity { index };
+                entries.push(self.append_document(&mut bundle, path, document, locator, token, writer).await?);
+            }
+        }
+        for WithRaw { value: dependency, raw } in &snapshot.dependencies {
+            let dependency_dir = path_component(&dependency.id);
+            let file = response_file(&format!("responses/dependencies/{}.json", dependency_dir), raw.expose(), dependency.fetched_at)?;
+            entries.push(bundle.append_file(file, writer).await?);
+            for (index, document) in dependency.identity_details.identity_files().iter().enumerate() {
+                let locator = DocumentLocator::DependencyIdentity {
+                    dependency_id: dependency.id.clone(),
+                    index,
+                };
+                let path = format!("documents/dependencies/{}/{}-{}", dependency_dir, index, document.type_label());
+                entries.push(self.append_document(&mut bundle, path, document, locator, token, writer).await?);
+            }
+        }
+
+        let manifest = EvidenceManifest {
+            format_version: EVIDENCE_MANIFEST_FORMAT_VERSION,
+            subject_id: snapshot.subject_id().cloned(),
+            created_at,
+            encrypted: !options.encrypt_to.is_empty(),
+            entries,
+        };
+        let manifest_json = to_json_vec(&manifest)?;
+        bundle.append("manifest.json", &manifest_json)?;
+        writer.write_all(&bundle.finish()?).await?;
+        writer.flush().await?;
+
+        Ok(manifest)
+    }
+
+    async fn append_document<D: Document, W: AsyncWrite + Unpin>(
+        &self,
+        bundle: &mut BundleWriter,
+        path: String,
+        document: &D,
+        locator: DocumentLocator,
+        token: &str,
+        writer: &mut W,
+    ) -> Result<ManifestEntry> {
+        let body = self.open_document_refetching(document, &locator, token).await?;
+        let path = format!("{}.{}", path, body.media_type().extension());
+        let download = match body.content_length() {
+            Some(size) => {
+                let mut entry = bundle.begin_entry(&path, size, writer)?;
+                let download = body.copy_to(&mut entry).await?;
+                entry.finish().await?;
+                download
+            },
+            None => {
+                let mut content = Vec::new();
+                let download = body.copy_to(&mut content).await?;
+                bundle.append(&path, &content)?;
+                bundle.pass_output(writer).await?;
+                download
+            },
+        };
+        Ok(ManifestEntry {
+            path,
+            kind: ManifestEntryKind::Document,
+            document_type: Some(document.type_label()),
+            locator: Some(locator),
+            media_type: Some(download.media_type),
+            size: download.size,
+            sha256: download.sha256,
+            fetched_at: Some(self.clock.now()),
+        })
+    }
+}
+
+/// API IDs as a single path component. Anything but ASCII letters, digits, `-` and `_` is replaced,
+/// with a hash of the ID appended so that different IDs don't end up in the same file.
+fn path_component(id: &str) -> String {
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return id.to_string();
+    }
+    let sanitized: String = id.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    let hash = format!("{:x}", Sha256::digest(id.as_bytes()));
+    format!("{}-{}", sanitized, &hash[..8])
+}
+
+fn response_file(path: &str, raw: &serde_json::Value, fetched_at: Option<DateTime>) -> Result<BundleFile> {
+    let content = to_json_vec(raw)?;
+    Ok(BundleFile {
+        entry: ManifestEntry {
+            path: path.to_string(),
+            kind: ManifestEntryKind::Response,
+            document_type: None,
+            locator: None,
+            media_type: None,
+            size: content.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&content)),
+            fetched_at,
+        },
+        content,
+    })
+}
+
+const BLOCK_SIZE: u64 = 512;
+
+/// Bytes written by the archive, waiting to be passed on to the async writer
+#[derive(Clone, Default)]
+struct OutputBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl OutputBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum BundleSink {
+    Plain(OutputBuffer),
+    Encrypted(age::stream::StreamWriter<OutputBuffer>),
+}
+
+impl Write for BundleSink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            BundleSink::Plain(output) => output.write(buf),
+            BundleSink::Encrypted(output) => output.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            BundleSink::Plain(output) => output.flush(),
+            BundleSink::Encrypted(output) => output.flush(),
+        }
+    }
+}
+
+/// Gzipped tar archive, optionally age encrypted, whose output is collected in an `OutputBuffer`
+struct BundleWriter {
+    archive: tar::Builder<GzEncoder<BundleSink>>,
+    output: OutputBuffer,
+    mtime: u64,
+}
+
+impl BundleWriter {
+    fn new(encrypt_to: &[String], created_at: DateTime) -> Result<BundleWriter> {
+        let output = OutputBuffer::default();
+        let sink = if encrypt_to.is_empty() {
+            BundleSink::Plain(output.clone())
+        } else {
+            let recipients = encrypt_to
+                .iter()
+                .map(|key| {
+                    key.parse::<age::x25519::Recipient>()
+                        .map_err(|e| Error::EncryptionError(format!("invalid recipient '{}': {}", key, e)))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let encryptor =
+                age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient)).map_err(|e| Error::EncryptionError(e.to_string()))?;
+            BundleSink::Encrypted(encryptor.wrap_output(output.clone())?)
+        };
+        Ok(BundleWriter {
+            archive: tar::Builder::new(GzEncoder::new(sink, Compression::default())),
+            output,
+            mtime: created_at.unix_timestamp().max(0) as u64,
+        })
+    }
+
+    fn header(&self, size: u64) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(size);
+        header.set_mode(0o600);
+        header.set_mtime(self.mtime);
+        header
+    }
+
+    fn append(&mut self, path: &str, content: &[u8]) -> io::Result<()> {
+        let mut header = self.header(content.len() as u64);
+        self.archive.append_data(&mut header, path, content)
+    }
+
+    /// Appends the file and passes the output produced so far on to `writer`, returning the file's manifest entry
+    async fn append_file<W: AsyncWrite + Unpin>(&mut self, file: BundleFile, writer: &mut W) -> Result<ManifestEntry> {
+        self.append(&file.entry.path, &file.content)?;
+        self.pass_output(writer).await?;
+        Ok(file.entry)
+    }
+
+    async fn pass_output<W: AsyncWrite + Unpin>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.output.take()).await
+    }
+
+    /// Starts an entry of `size` bytes, whose content is then written to the returned `EntryWriter`
+    fn begin_entry<'a, W: AsyncWrite + Unpin>(&'a mut self, path: &str, size: u64, writer: &'a mut W) -> io::Result<EntryWriter<'a, W>> {
+        let mut header = self.header(size);
+        // With empty data only the header (and a long name entry if the path needs one) is written
+        self.archive.append_data(&mut header, path, io::empty())?;
+        Ok(EntryWriter {
+            bundle: self,
+            writer,
+            pending: Vec::new(),
+            size,
+            remaining: size,
+        })
+    }
+
+    /// Ends the archive, returning the output not taken yet
+    fn finish(self) -> Result<Vec<u8>> {
+        match self.archive.into_inner()?.finish()? {
+            BundleSink::Plain(_) => {},
+            BundleSink::Encrypted(output) => {
+                output.finish()?;
+            },
+        }
+        Ok(self.output.take())
+    }
+}
+
+/// Content of an archive entry being written, passed through the archive to `writer` as it is written
+struct EntryWriter<'a, W> {
+    bundle: &'a mut BundleWriter,
+    writer: &'a mut W,
+    /// Archive output not accepted by `writer` yet
+    pending: Vec<u8>,
+    /// Size announced in the entry's header
+    size: u64,
+    /// Bytes of content still expected
+    remaining: u64,
+}
+
+impl<W: AsyncWrite + Unpin> EntryWriter<'_, W> {
+    /// Ends the entry, failing if less content was written than its header announced
+    async fn finish(mut self) -> io::Result<()> {
+        if self.remaining > 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "entry content is shorter than its size"));
+        }
+        // Pads the content to whole tar blocks, as `append_data` does
+        let padding = (BLOCK_SIZE - self.size % BLOCK_SIZE) % BLOCK_SIZE;
+        self.bundle.archive.get_mut().write_all(&[0; BLOCK_SIZE as usize][..padding as usize])?;
+        let output = self.bundle.output.take();
+        self.pending.extend(output);
+        self.flush().await
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context) -> Poll<io::Result<()>> {
+        while !self.pending.is_empty() {
+            let written = ready!(Pin::new(&mut *self.writer).poll_write(cx, &self.pending))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.pending.drain(..written);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for EntryWriter<'_, W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        if buf.len() as u64 > this.remaining {
+            return Poll::Ready(Err(io::Error::new(io::ErrorKind::InvalidData, "entry content is longer than its size")));
+        }
+        this.bundle.archive.get_mut().write_all(buf)?;
+        this.remaining -= buf.len() as u64;
+        this.pending = this.bundle.output.take();
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_pending(cx))?;
+        Pin::new(&mut *this.writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use time::OffsetDateTime;
+
+    #[test]
+    fn archive_contains_files_and_manifest() {
+        let raw = serde_json::json!({
+            "id": "subject",
+            "type": "individual",
+            "profile": crate::fixtures::individual_profile("Alice", "Smith"),
+            "user_id": "VXNlcjox",
+            "user_profile": crate::fixtures::individual_profile("Alice", "Smith"),
+            "user_providing_for": "self",
+            "access_expires_at": null,
+            "access_revoked_by": null,
+        });
+        let profile: ProfileResponse = serde_json::from_value(raw.clone()).unwrap();
+        let file = response_file("responses/profile.json", &raw, None).unwrap();
+        let manifest = EvidenceManifest {
+            format_version: EVIDENCE_MANIFEST_FORMAT_VERSION,
+            subject_id: Some(profile.id.clone()),
+            created_at: OffsetDateTime::UNIX_EPOCH,
+            encrypted: false,
+            entries: vec![file.entry.clone()],
+        };
+        let manifest_json = serde_json::to_vec(&manifest).unwrap();
+
+        let mut bundle = BundleWriter::new(&[], manifest.created_at).unwrap();
+        bundle.append(&file.entry.path, &file.content).unwrap();
+        let mut output = bundle.output.take();
+        bundle.append("manifest.json", &manifest_json).unwrap();
+        output.extend(bundle.finish().unwrap());
+
+        let entries = archive_entries(&output);
+        assert_eq!(entries[0].0, "responses/profile.json");
+        assert_eq!(format!("{:x}", Sha256::digest(&entries[0].1)), manifest.entries[0].sha256);
+        assert_eq!(entries[1], ("manifest.json".to_string(), manifest_json));
+    }
+
+    fn archive_entries(output: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut archive = tar::Archive::new(GzDecoder::new(output));
+        let mut entries = Vec::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).unwrap();
+            entries.push((entry.path().unwrap().to_string_lossy().to_string(), content));
+        }
+        entries
+    }
+
+    #[tokio::test]
+    async fn streamed_entries_are_written_in_place() {
+        let path = format!("documents/dependencies/{}/0-passport.pdf", "a".repeat(120));
+        let content: Vec<u8> = (0..1500).map(|i| (i % 251) as u8).collect();
+        let mut output = Vec::new();
+
+        let mut bundle = BundleWriter::new(&[], OffsetDateTime::UNIX_EPOCH).unwrap();
+        let mut entry = bundle.begin_entry(&path, content.len() as u64, &mut output).unwrap();
+        for chunk in content.chunks(400) {
+            entry.write_all(chunk).await.unwrap();
+        }
+        entry.finish().await.unwrap();
+        bundle.append("manifest.json", b"{}").unwrap();
+        output.extend(bundle.finish().unwrap());
+
+        assert_eq!(archive_entries(&output), vec![(path, content), ("manifest.json".to_string(), b"{}".to_vec())]);
+    }
+
+    #[tokio::test]
+    async fn streamed_entries_must_match_their_size() {
+        let mut output = Vec::new();
+        let mut bundle = BundleWriter::new(&[], OffsetDateTime::UNIX_EPOCH).unwrap();
+
+        let mut entry = bundle.begin_entry("too-long.pdf", 3, &mut output).unwrap();
+        assert_eq!(entry.write_all(b"%PDF-").await.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        let mut entry = bundle.begin_entry("too-short.pdf", 10, &mut output).unwrap();
+        entry.write_all(b"%PDF-").await.unwrap();
+        assert_eq!(entry.finish().await.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn ids_cannot_escape_their_directory() {
+        assert_eq!(path_component("VXNlcjox_a-1"), "VXNlcjox_a-1");
+        let escaped = path_component("../../etc/passwd");
+        assert!(escaped.starts_with("______etc_passwd-"));
+        assert_ne!(path_component("a/b"), path_component("a_b"));
+        assert_ne!(path_component(""), "");
+    }
+}
@@ -0,0 +1,180 @@
+//! JSON fixtures shared by the unit tests
+
+use serde_json::{json, Value};
+
+/// `value` with the fields of `overrides` set, recursing into objects, e.g.
+/// `with(individual_identity("Alice", "Smith"), json!({ "residence_location": { "state": null } }))`
+pub fn with(mut value: Value, overrides: Value) -> Value {
+    merge(&mut value, overrides);
+    value
+}
+
+fn merge(value: &mut Value, overrides: Value) {
+    match (value, overrides) {
+        (Value::Object(fields), Value::Object(overrides)) => {
+            for (key, field_override) in overrides {
+                match fields.get_mut(&key) {
+                    Some(field) => merge(field, field_override),
+                    None => {
+                        fields.insert(key, field_override);
+                    },
+                }
+            }
+        },
+        (value, overrides) => *value = overrides,
+    }
+}
+
+pub fn individual_profile(first_name: &str, last_name: &str) -> Value {
+    json!({
+        "first_name": first_name,
+        "last_name": last_name,
+        "email": format!("{}@example.com", first_name.to_lowercase()),
+    })
+}
+
+pub fn location(country: &str) -> Value {
+    json!({
+        "address_one": "1 Main St",
+        "address_two": "",
+        "city": "Springfield",
+        "region": null,
+        "postal_code": "62701",
+        "state": "IL",
+        "country": country,
+    })
+}
+
+pub fn individual_owner(id: &str, first_name: &str, last_name: &str, ownership_percent: u32) -> Value {
+    json!({
+        "id": id,
+        "type": "individual",
+        "ownership_percent": ownership_percent,
+        "profile": individual_profile(first_name, last_name),
+    })
+}
+
+pub fn business_owner(id: &str, name: &str, ownership_percent: u32) -> Value {
+    json!({
+        "id": id,
+        "type": "business",
+        "ownership_percent": ownership_percent,
+        "profile": {
+            "name": name,
+            "business_type": "C Corporation",
+            "primary_contact": null,
+        },
+    })
+}
+
+pub fn individual_identity(first_name: &str, last_name: &str) -> Value {
+    json!({
+        "birth_date": "1980-05-17",
+        "citizenship_country": "US",
+        "completed_at": "2023-01-10T12:00:00Z",
+        "created_by": individual_profile(first_name, last_name),
+        "domicile_location": location("US"),
+        "email": format!("{}@example.com", first_name.to_lowercase()),
+        "expires_at": "2024-01-10T12:00:00Z",
+        "first_name": first_name,
+        "last_name": last_name,
+        "identity_files": [],
+        "marital_status": "single",
+        "phone": "+12025550123",
+        "residence_location": location("US"),
+        "risk_monitor_matches": [],
+        "us_tax_id": "123-45-6789",
+        "foreign_tax_id": null,
+        "user_session": { "maybe_anonymizing_proxy": false },
+    })
+}
+
+pub fn control_person(id: &str, first_name: &str, last_name: &str, title: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "individual",
+        "title": title,
+        "profile": individual_profile(first_name, last_name),
+    })
+}
+
+pub fn business_identity(name: &str, owners: Vec<Value>, control_persons: Vec<Value>) -> Value {
+    json!({
+        "business_type": "C Corporation",
+        "completed_at": "2023-01-10T12:00:00Z",
+        "control_persons": control_persons,
+        "created_by": individual_profile("Jane", "Doe"),
+        "direct_beneficial_owners": owners,
+        "expires_at": "2024-01-10T12:00:00Z",
+        "foreign_tax_id": "",
+        "identity_files": [],
+        "incorporation_country": "US",
+        "incorporation_state": "DE",
+        "name": name,
+        "primary_contact": individual_profile("Jane", "Doe"),
+        "principal_location": location("US"),
+        "risk_monitor_matches": [],
+        "us_tax_id": "12-3456789",
+        "user_session": { "maybe_anonymizing_proxy": false },
+    })
+}
+
+/// `flags` are the names of the boolean fields to set, e.g. `pep`
+pub fn individual_risk_match(first_name: &str, last_name: &str, score: u8, flags: &[&str]) -> Value {
+    let mut value = json!({
+        "adverse_media": false,
+        "birth_date": "1980-05-17",
+        "currently_sanctioned": false,
+        "deceased": false,
+        "disqualified_director": false,
+        "financial_regulator": false,
+        "insolvent": false,
+        "first_name": first_name,
+        "middle_name": "",
+        "last_name": last_name,
+        "law_enforcement": false,
+        "nationality": "United States",
+        "pep": false,
+        "previously_sanctioned": false,
+        "score": score,
+    });
+    for flag in flags {
+        value[*flag] = Value::Bool(true);
+    }
+    value
+}
+
+pub fn individual_accreditation(id: &str, status: &str, created_at: i64, expires_at: Option<i64>) -> Value {
+    json!({
+        "id": id,
+        "status": status,
+        "expires_at": expires_at,
+        "assertion_type": "income",
+        "created_at": created_at,
+        "certified_at": expires_at.map(|_| created_at),
+        "first_name": "Alice",
+        "last_name": "Smith",
+        "documents": [],
+    })
+}
+
+pub fn accreditations_response(entity_type: &str, accreditations: Vec<Value>, indicated_unaccredited: Option<i64>) -> Value {
+    json!({
+        "id": "subject",
+        "type": entity_type,
+        "user_id": "VXNlcjox",
+        "indicated_unaccredited": indicated_unaccredited,
+        "accreditations": accreditations,
+    })
+}
+
+pub fn dependency_identity(id: &str, entity_type: &str, identity_details: Value) -> Value {
+    json!({
+        "id": id,
+        "type": entity_type,
+        "identity_details": identity_details,
+        "user_id": "VXNlcjox",
+        "access_expires_at": null,
+        "access_revoked_by": null,
+    })
+}
@@ -0,0 +1,179 @@
+use time::Duration;
+
+use crate::clock::Clock;
+use crate::types::*;
+use json_api_client::types::*;
+
+/// Default for `FreshnessPolicy::expiring_soon`
+pub const DEFAULT_EXPIRING_SOON_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Freshness {
+    Valid {
+        expires_at: DateTime,
+    },
+    /// Expires within the policy's `expiring_soon` window, the user should be asked to re-verify
+    ExpiringSoon {
+        expires_at: DateTime,
+    },
+    Expired {
+        expired_at: DateTime,
+    },
+}
+
+/// Decides whether an identity can be reused or must be verified again
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FreshnessPolicy {
+    /// How long before expiry an identity counts as expiring soon
+    pub expiring_soon: Duration,
+    /// If set, identities also expire this long after `completed_at`, even if the API allows longer
+    pub max_age: Option<Duration>,
+}
+
+impl Default for FreshnessPolicy {
+    fn default() -> Self {
+        FreshnessPolicy {
+            expiring_soon: Duration::days(DEFAULT_EXPIRING_SOON_DAYS),
+            max_age: None,
+        }
+    }
+}
+
+/// When a stored subject next needs attention
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttentionDue<'a> {
+    pub subject_id: &'a str,
+    /// Start of the expiring soon window, the expiry, or now for identities that already expired
+    pub at: DateTime,
+    pub freshness: Freshness,
+}
+
+impl IdentityDetails {
+    pub fn completed_at(&self) -> DateTime {
+        match self {
+            IdentityDetails::Individual(individual) => individual.completed_at,
+            IdentityDetails::Business(business) => business.completed_at,
+        }
+    }
+
+    pub fn expires_at(&self) -> DateTime {
+        match self {
+            IdentityDetails::Individual(individual) => individual.expires_at,
+            IdentityDetails::Business(business) => business.expires_at,
+        }
+    }
+}
+
+impl FreshnessPolicy {
+    /// The identity's `expires_at`, or the end of `max_age` if that is earlier
+    pub fn expires_at(&self, details: &IdentityDetails) -> DateTime {
+        match self.max_age {
+            Some(max_age) => details.expires_at().min(details.completed_at().saturating_add(max_age)),
+            None => details.expires_at(),
+        }
+    }
+
+    pub fn evaluate(&self, details: &IdentityDetails, clock: &impl Clock) -> Freshness {
+        let now = clock.now();
+        let expires_at = self.expires_at(details);
+        if expires_at <= now {
+            Freshness::Expired { expired_at: expires_at }
+        } else if expires_at.saturating_sub(self.expiring_soon) <= now {
+            Freshness::ExpiringSoon { expires_at }
+        } else {
+            Freshness::Valid { expires_at }
+        }
+    }
+
+    /// Next time the identity needs attention: when it starts expiring soon, when it expires, or now if it already expired
+    pub fn next_attention(&self, details: &IdentityDetails, clock: &impl Clock) -> DateTime {
+        match self.evaluate(details, clock) {
+            Freshness::Valid { expires_at } => expires_at.saturating_sub(self.expiring_soon),
+            Freshness::ExpiringSoon { expires_at } => expires_at,
+            Freshness::Expired { .. } => clock.now(),
+        }
+    }
+
+    /// Attention dates of stored subjects, soonest first. Intended to be run periodically by a scheduler.
+    pub fn schedule<'a, I>(&self, subjects: I, clock: &impl Clock) -> Vec<AttentionDue<'a>>
+    where
+        I: IntoIterator<Item = (&'a str, &'a IdentityDetails)>,
+    {
+        let mut due: Vec<AttentionDue> = subjects
+            .into_iter()
+            .map(|(subject_id, details)| AttentionDue {
+                subject_id,
+                at: self.next_attention(details, clock),
+                freshness: self.evaluate(details, clock),
+            })
+            .collect();
+        due.sort_by(|a, b| a.at.cmp(&b.at).then_with(|| a.subject_id.cmp(b.subject_id)));
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::fixtures::*;
+    use time::macros::datetime;
+
+    // The fixtures complete on 2023-01-10 and expire on 2024-01-10
+    fn identity() -> IdentityDetails {
+        IdentityDetails::Individual(serde_json::from_value(individual_identity("Alice", "Smith")).unwrap())
+    }
+
+    #[test]
+    fn identities_far_from_expiry_are_valid() {
+        assert_eq!(
+            FreshnessPolicy::default().evaluate(&identity(), &FixedClock(datetime!(2023-06-01 00:00 UTC))),
+            Freshness::Valid {
+                expires_at: datetime!(2024-01-10 12:00 UTC)
+            }
+        );
+    }
+
+    #[test]
+    fn identities_close_to_expiry_are_expiring_soon() {
+        assert_eq!(
+            FreshnessPolicy::default().evaluate(&identity(), &FixedClock(datetime!(2023-12-20 00:00 UTC))),
+            Freshness::ExpiringSoon {
+                expires_at: datetime!(2024-01-10 12:00 UTC)
+            }
+        );
+    }
+
+    #[test]
+    fn max_age_expires_identities_before_their_expiry() {
+        let strict = FreshnessPolicy {
+            max_age: Some(Duration::days(90)),
+            ..FreshnessPolicy::default()
+        };
+
+        assert_eq!(
+            strict.evaluate(&identity(), &FixedClock(datetime!(2023-06-01 00:00 UTC))),
+            Freshness::Expired {
+                expired_at: datetime!(2023-04-10 12:00 UTC)
+            }
+        );
+    }
+
+    #[test]
+    fn schedules_soonest_attention_first() {
+        let policy = FreshnessPolicy::default();
+        let clock = FixedClock(datetime!(2023-12-20 00:00 UTC));
+        let business = IdentityDetails::Business(serde_json::from_value(business_identity("Holding LLC", vec![], vec![])).unwrap());
+        let mut renewed = identity();
+        if let IdentityDetails::Individual(individual) = &mut renewed {
+            individual.expires_at = datetime!(2024-06-01 00:00 UTC);
+        }
+
+        let due = policy.schedule([("renewed", &renewed), ("holding", &business)], &clock);
+
+        assert_eq!(due[0].subject_id, "holding");
+        assert_eq!(due[0].at, datetime!(2024-01-10 12:00 UTC));
+        assert_eq!(due[1].subject_id, "renewed");
+        assert_eq!(due[1].at, datetime!(2024-05-02 00:00 UTC));
+    }
+}
@@ -0,0 +1,57 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Result;
+
+/// Serializes `value` as indented JSON. Failures, e.g. maps with non-string keys, are returned as `IoError`.
+pub(crate) fn to_json_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    Ok(serde_json::to_vec_pretty(value).map_err(io::Error::from)?)
+}
+
+/// Reads a JSON file, None if it doesn't exist
+pub(crate) fn read_json_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match fs::read(path) {
+        Ok(content) => Ok(Some(serde_json::from_slice(&content).map_err(io::Error::from)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replaces the file with `value` as JSON. It is written next to the file and renamed, so a crash doesn't leave a truncated file.
+pub(crate) fn write_json_file<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    let content = to_json_vec(value)?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("json-file-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn missing_files_read_as_none() {
+        assert_eq!(read_json_file::<Vec<u8>>(&temp_path("missing")).unwrap(), None);
+    }
+
+    #[test]
+    fn unserializable_values_are_errors_and_keep_the_file() {
+        let path = temp_path("unserializable");
+        write_json_file(&path, &BTreeMap::from([("key", 1)])).unwrap();
+
+        let unserializable = HashMap::from([((1, 2), "tuple keys")]);
+        assert!(matches!(write_json_file(&path, &unserializable), Err(Error::IoError(_))));
+        assert_eq!(read_json_file(&path).unwrap(), Some(BTreeMap::from([("key".to_string(), 1)])));
+        fs::remove_file(&path).unwrap();
+    }
+}
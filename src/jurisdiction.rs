@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::config::{self, CodeList};
+use crate::countries::country_code;
+use crate::types::*;
+use crate::Result;
+use json_api_client::types::*;
+
+/// Lists bundled with the crate, see `JurisdictionLists::bundled`
+pub const BUNDLED_JURISDICTION_LISTS: &str = include_str!("../assets/jurisdictions.toml");
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JurisdictionList {
+    pub name: String,
+    /// Where the list comes from, e.g. the publication and its date
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Country codes as sent by the API, e.g. `KP`
+    pub countries: CodeList,
+}
+
+/// Named lists of high-risk or sanctioned jurisdictions, e.g. in TOML:
+///
+/// ```toml
+/// [[lists]]
+/// name = "sanctioned"
+/// source = "internal compliance policy, 2023-05"
+/// countries = ["IR", "KP"]
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct JurisdictionLists {
+    pub lists: Vec<JurisdictionList>,
+}
+
+/// A country field whose value is on at least one list
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct JurisdictionHit {
+    /// Path of the field in the identity, e.g. `residence_location.country`.
+    /// Fields of owners' identities are prefixed with the owner, e.g. `direct_beneficial_owners[0].citizenship_country`.
+    pub field: String,
+    pub country: String,
+    pub lists: Vec<String>,
+}
+
+impl Default for JurisdictionLists {
+    fn default() -> Self {
+        JurisdictionLists::bundled()
+    }
+}
+
+impl JurisdictionLists {
+    /// The lists in `assets/jurisdictions.toml`, a snapshot to be reviewed before relying on it
+    pub fn bundled() -> JurisdictionLists {
+        JurisdictionLists::from_toml(BUNDLED_JURISDICTION_LISTS).expect("bundled jurisdiction lists are valid")
+    }
+
+    pub fn from_toml(lists: &str) -> Result<JurisdictionLists> {
+        config::from_toml("jurisdiction lists", lists)
+    }
+
+    pub fn from_json(lists: &str) -> Result<JurisdictionLists> {
+        config::from_json("jurisdiction lists", lists)
+    }
+
+    /// Loads JSON files by their `.json` extension, anything else as TOML
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<JurisdictionLists> {
+        config::from_file("jurisdiction lists", path.as_ref())
+    }
+
+    /// Names of the lists containing `country`
+    pub fn lists_for(&self, country: &str) -> Vec<String> {
+        self.lists
+            .iter()
+            .filter(|list| list.countries.contains(country))
+            .map(|list| list.name.clone())
+            .collect()
+    }
+
+    /// Checks every country field of the identity. For businesses, the identities of direct beneficial owners
+    /// found in `owner_identities` (keyed by owner ID) are checked too, including their own owners. An owner reached
+    /// through several branches is reported under each of them.
+    pub fn evaluate(&self, details: &IdentityDetails, owner_identities: &HashMap<EntityId, IdentityDetails>) -> Vec<JurisdictionHit> {
+        let mut hits = Vec::new();
+        self.evaluate_details("", details, owner_identities, &mut Vec::new(), &mut hits);
+        hits
+    }
+
+    fn evaluate_details<'a>(
+        &self,
+        prefix: &str,
+        details: &'a IdentityDetails,
+        owner_identities: &'a HashMap<EntityId, IdentityDetails>,
+        ancestors: &mut Vec<&'a str>,
+        hits: &mut Vec<JurisdictionHit>,
+    ) {
+        match details {
+            IdentityDetails::Individual(individual) => {
+                self.check(prefix, "citizenship_country", &individual.citizenship_country, hits);
+                self.check(prefix, "residence_location.country", &individual.residence_location.country, hits);
+                self.check(prefix, "domicile_location.country", &individual.domicile_location.country, hits);
+            },
+            IdentityDetails::Business(business) => {
+                self.check(prefix, "incorporation_country", &business.incorporation_country, hits);
+                self.check(prefix, "principal_location.country", &business.principal_location.country, hits);
+                for (index, owner) in business.direct_beneficial_owners.iter().enumerate() {
+                    let Some((owner_id, owner_details)) = owner.id.as_ref().and_then(|id| owner_identities.get_key_value(id)) else {
+                        continue;
+                    };
+                    // Ownership structures can be circular, an owner is skipped only when it owns itself along this path
+                    if ancestors.contains(&owner_id.as_str()) {
+                        continue;
+                    }
+                    let owner_prefix = format!("{}direct_beneficial_owners[{}].", prefix, index);
+                    ancestors.push(owner_id.as_str());
+                    self.evaluate_details(&owner_prefix, owner_details, owner_identities, ancestors, hits);
+                    ancestors.pop();
+                }
+            },
+        }
+    }
+
+    fn check(&self, prefix: &str, field: &str, country: &CountryCode, hits: &mut Vec<JurisdictionHit>) {
+        let Some(country) = country_code(country) else {
+            return;
+        };
+        let lists = self.lists_for(&country);
+        if !lists.is_empty() {
+            hits.push(JurisdictionHit {
+                field: format!("{}{}", prefix, field),
+                country,
+                lists,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+
+    fn resident_of(country: &str) -> IdentityDetails {
+        let alice = with(individual_identity("Alice", "Smith"), json!({ "residence_location": { "country": country } }));
+        IdentityDetails::Individual(serde_json::from_value(alice).unwrap())
+    }
+
+    #[test]
+    fn bundled_lists_load() {
+        let lists = JurisdictionLists::bundled();
+        assert_eq!(lists.lists_for("kp"), vec!["fatf_call_for_action".to_string(), "ofac_comprehensive_sanctions".to_string()]);
+        assert!(lists.lists_for("US").is_empty());
+    }
+
+    #[test]
+    fn unknown_list_fields_are_rejected() {
+        assert!(JurisdictionLists::from_toml("[[lists]]\nname = \"typo\"\ncountry = [\"IR\"]").is_err());
+    }
+
+    #[test]
+    fn hits_name_fields_of_the_identity_and_its_owners() {
+        let lists = JurisdictionLists::from_json(r#"{ "lists": [{ "name": "sanctioned", "countries": ["IR"] }] }"#).unwrap();
+        let business =
+            business_identity("Holding LLC", vec![individual_owner("alice", "Alice", "Smith", 60), business_owner("holding", "Holding LLC", 40)], vec![]);
+        let business = IdentityDetails::Business(serde_json::from_value(with(business, json!({ "principal_location": { "country": "IR" } }))).unwrap());
+        // The business owns itself, which must not loop
+        let holding = business_identity("Holding LLC", vec![business_owner("holding", "Holding LLC", 100)], vec![]);
+        let holding = IdentityDetails::Business(serde_json::from_value(with(holding, json!({ "incorporation_country": "IR" }))).unwrap());
+        let owners = HashMap::from([("alice".to_string(), resident_of("IR")), ("holding".to_string(), holding)]);
+
+        let fields: Vec<String> = lists.evaluate(&business, &owners).into_iter().map(|hit| hit.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "principal_location.country",
+                "direct_beneficial_owners[0].residence_location.country",
+                "direct_beneficial_owners[1].incorporation_country",
+            ]
+        );
+    }
+
+    #[test]
+    fn shared_owners_are_reported_under_each_branch() {
+        let lists = JurisdictionLists::from_json(r#"{ "lists": [{ "name": "sanctioned", "countries": ["IR"] }] }"#).unwrap();
+        let business = business_identity("Top LLC", vec![business_owner("left", "Left LLC", 50), business_owner("right", "Right LLC", 50)], vec![]);
+        let business: IdentityDetails = IdentityDetails::Business(serde_json::from_value(business).unwrap());
+        let branch = |name: &str| {
+            IdentityDetails::Business(serde_json::from_value(business_identity(name, vec![individual_owner("alice", "Alice", "Smith", 100)], vec![])).unwrap())
+        };
+        let owners = HashMap::from([
+            ("left".to_string(), branch("Left LLC")),
+            ("right".to_string(), branch("Right LLC")),
+            ("alice".to_string(), resident_of("IR")),
+        ]);
+
+        let fields: Vec<String> = lists.evaluate(&business, &owners).into_iter().map(|hit| hit.field).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "direct_beneficial_owners[0].direct_beneficial_owners[0].residence_location.country",
+                "direct_beneficial_owners[1].direct_beneficial_owners[0].residence_location.country",
+            ]
+        );
+    }
+}
@@ -1,16 +1,80 @@
+mod access;
+mod accreditation;
+mod address;
+mod assertion;
+mod clock;
+mod config;
+mod countries;
+mod datetime;
+mod document;
+mod drift;
+mod eligibility;
 mod error;
+mod evidence;
+#[cfg(test)]
+mod fixtures;
+mod freshness;
+mod json_file;
+mod jurisdiction;
+mod ownership;
+mod ownership_export;
+mod purge;
+mod raw;
+mod reliance;
+mod review;
+mod risk;
+mod risk_policy;
+mod secret;
+mod triage;
 mod types;
+mod validation;
+mod values;
 
+pub use access::*;
+pub use accreditation::*;
+pub use address::*;
+pub use assertion::*;
+pub use clock::*;
+pub use config::*;
+pub use countries::*;
+pub use datetime::*;
+pub use document::*;
+pub use drift::*;
+pub use eligibility::*;
 pub use error::*;
+pub use evidence::*;
+pub use freshness::*;
 use json_api_client::*;
+pub use jurisdiction::*;
+pub use ownership::*;
+pub use ownership_export::*;
+pub use purge::*;
+pub use raw::*;
+pub use reliance::*;
 use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+pub use review::*;
+pub use risk::*;
+pub use risk_policy::*;
+pub use secret::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+pub use triage::*;
 pub use types::*;
+pub use validation::*;
+pub use values::*;
 
 pub use json_api_client::{AccessToken, AuthorizationCode, RefreshToken, StandardToken, Token};
 
 pub struct Client {
     api: ApiClient,
+    http: reqwest::Client,
     scopes: Vec<Scope>,
+    max_document_size: u64,
+    drift_handler: Option<SchemaDriftHandler>,
+    clock: Arc<dyn Clock>,
+    access: AccessRegistry,
     //client_id: String,
     //client_secret: String,
 }
@@ -33,12 +97,50 @@ impl Client {
 
         Ok(Client {
             api: client,
+            http: reqwest::Client::new(),
             scopes: scopes.to_vec(),
+            max_document_size: DEFAULT_MAX_DOCUMENT_SIZE,
+            drift_handler: None,
+            clock: Arc::new(SystemClock),
+            access: AccessRegistry::new(),
             //client_id: client_id.to_owned(),
             //client_secret: client_secret.to_owned(),
         })
     }
 
+    /// Limits the size of downloaded documents, `DEFAULT_MAX_DOCUMENT_SIZE` by default
+    pub fn with_max_document_size(mut self, max_document_size: u64) -> Client {
+        self.max_document_size = max_document_size;
+        self
+    }
+
+    /// Reports fields that differ between the API responses and the structs of this crate to `handler`.
+    /// Responses are parsed as usual, the handler is only called if there is a difference.
+    pub fn with_schema_drift_handler<F>(mut self, handler: F) -> Client
+    where
+        F: Fn(&SchemaDrift) + Send + Sync + 'static,
+    {
+        self.drift_handler = Some(Arc::new(handler));
+        self
+    }
+
+    /// Replaces the system clock used for fetch times and URL expiry checks, e.g. with a `FixedClock` in tests
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Client {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Shares access state with e.g. the webhook handler, which can pass `AccessRevocationScheduled` events to `AccessRegistry::record_webhook`
+    pub fn with_access_registry(mut self, registry: AccessRegistry) -> Client {
+        self.access = registry;
+        self
+    }
+
+    /// Access state of the tokens used so far. Requests with a token known to be revoked fail with `AccessRevoked`.
+    pub fn access_registry(&self) -> &AccessRegistry {
+        &self.access
+    }
+
     fn oauth_header(token: &str) -> HeaderMap {
         let auth_header = format!("Bearer {}", token);
         let mut headers = HeaderMap::new();
@@ -48,20 +150,41 @@ impl Client {
         headers
     }
 
-    async fn get<T>(&self, path: &str, token: &str) -> Result<T>
+    /// `endpoint` names the request in drift reports, `path` with IDs replaced by placeholders
+    async fn get<T>(&self, endpoint: &str, path: &str, token: &str) -> Result<WithRaw<T>>
     where
-        T: JsonResponse,
+        T: JsonResponse + DeserializeOwned + Serialize + FetchStamp + ReportsAccess,
     {
+        // Read before the request, so `fetched_at` is never later than when the API served the data
+        let fetched_at = self.clock.now();
+        self.access.ensure_not_revoked(token, fetched_at)?;
         let header = Client::oauth_header(token);
-        self.api.get(path, None, Some(header)).await.map_err(Error::from)
+        let raw: serde_json::Value = self.api.get(path, None, Some(header)).await.map_err(Error::from)?;
+        let mut response: WithRaw<T> = parse_response(raw)?;
+        if let Some(handler) = &self.drift_handler {
+            match serde_json::to_value(&response.value) {
+                Ok(typed) => {
+                    if let Some(drift) = detect_schema_drift(endpoint, response.raw.expose(), &typed) {
+                        handler(&drift);
+                    }
+                },
+                // Drift detection is best effort, it doesn't fail the request
+                Err(e) => log::warn!("skipped schema drift detection for {}: {}", endpoint, e),
+            }
+        }
+        if let Some((subject_id, state)) = response.value.reported_access(fetched_at) {
+            self.access.record(token, Some(subject_id), state);
+        }
+        response.value.stamp(fetched_at);
+        Ok(response)
     }
 
-    pub async fn exchange_code(&self, code: AuthorizationCode) -> Result<StandardToken> {
-        self.api.exchange_code(code).await.map_err(Error::from)
+    pub async fn exchange_code(&self, code: AuthorizationCode) -> Result<RedactedToken> {
+        self.api.exchange_code(code).await.map(RedactedToken::from).map_err(Error::from)
     }
 
-    pub async fn refresh_token(&self, token: &RefreshToken) -> Result<StandardToken> {
-        self.api.refresh(token).await.map_err(Error::from)
+    pub async fn refresh_token(&self, token: &RefreshToken) -> Result<RedactedToken> {
+        self.api.refresh(token).await.map(RedactedToken::from).map_err(Error::from)
     }
 
     fn ensure_scope(&self, scope: Scope) -> Result<()> {
@@ -72,24 +195,107 @@ impl Client {
     }
 
     pub async fn get_profile(&self, token: &str) -> Result<ProfileResponse> {
+        self.get_profile_with_raw(token).await.map(WithRaw::into_value)
+    }
+
+    /// Same as `get_profile`, also returning the JSON as received
+    pub async fn get_profile_with_raw(&self, token: &str) -> Result<WithRaw<ProfileResponse>> {
         self.ensure_scope(Scope::Profile)?;
-        self.get("me", token).await
+        self.get("me", "me", token).await
     }
 
     pub async fn get_accreditations(&self, token: &str) -> Result<AccreditationsResponse> {
+        self.get_accreditations_with_raw(token).await.map(WithRaw::into_value)
+    }
+
+    /// Same as `get_accreditations`, also returning the JSON as received
+    pub async fn get_accreditations_with_raw(&self, token: &str) -> Result<WithRaw<AccreditationsResponse>> {
         self.ensure_scope(Scope::AccreditationStatus)?;
-        self.get("accreditations", token).await
+        self.get("accreditations", "accreditations", token).await
     }
 
     pub async fn get_identity(&self, token: &str) -> Result<IdentityResponse> {
+        self.get_identity_with_raw(token).await.map(WithRaw::into_value)
+    }
+
+    /// Same as `get_identity`, also returning the JSON as received
+    pub async fn get_identity_with_raw(&self, token: &str) -> Result<WithRaw<IdentityResponse>> {
         self.ensure_scope(Scope::Identity)?;
-        self.get("identity", token).await
+        self.get("identity", "identity", token).await
     }
 
     /// dependency_id: ID from ControlPersonReference or BeneficialOwnerReference
     pub async fn get_dependency_identity(&self, dependency_id: &str, token: &str) -> Result<DependencyIdentityResponse> {
+        self.get_dependency_identity_with_raw(dependency_id, token).await.map(WithRaw::into_value)
+    }
+
+    /// Same as `get_dependency_identity`, also returning the JSON as received
+    pub async fn get_dependency_identity_with_raw(&self, dependency_id: &str, token: &str) -> Result<WithRaw<DependencyIdentityResponse>> {
         let path = format!("identity/{}", dependency_id);
-        self.get(&path, token).await
+        self.get("identity/{id}", &path, token).await
+    }
+
+    /// Streams a document into `writer`, returning its size, SHA-256 and media type.
+    /// Fails with `DocumentUrlExpired` if the download URL is known to have expired,
+    /// and with `UnexpectedDocumentContent` if the body is not a PDF, JPEG, PNG or HEIC file (nothing is written in that case).
+    /// `DocumentTooLarge` may be returned after part of the content has been written.
+    pub async fn download_document<D, W>(&self, document: &D, writer: &mut W) -> Result<DocumentDownload>
+    where
+        D: Document,
+        W: AsyncWrite + Unpin,
+    {
+        self.open_document(document).await?.copy_to(writer).await
+    }
+
+    /// Requests a document and detects its media type, leaving the rest of the content unread
+    async fn open_document<D: Document>(&self, document: &D) -> Result<DocumentBody> {
+        if let Some(expires_at) = document.download_url_expires_at() {
+            if expires_at <= self.clock.now() {
+                return Err(Error::ApiError(ErrorKind::DocumentUrlExpired(expires_at)));
+            }
+        }
+
+        let response = self.http.get(document.download_url()).send().await?.error_for_status()?;
+        DocumentBody::open(response, self.max_document_size).await
+    }
+
+    /// Same as `download_document`, but if the download URL has expired (or its fetch time is unknown)
+    /// the resource identified by `locator` is fetched again to get a fresh URL
+    pub async fn download_document_refetching<D, W>(&self, document: &D, locator: &DocumentLocator, token: &str, writer: &mut W) -> Result<DocumentDownload>
+    where
+        D: Document,
+        W: AsyncWrite + Unpin,
+    {
+        self.open_document_refetching(document, locator, token).await?.copy_to(writer).await
+    }
+
+    /// `open_document`, refreshing the download URL like `download_document_refetching`
+    async fn open_document_refetching<D: Document>(&self, document: &D, locator: &DocumentLocator, token: &str) -> Result<DocumentBody> {
+        let now = self.clock.now();
+        if document.download_url_expires_at().map(|expires_at| expires_at > now).unwrap_or(false) {
+            return self.open_document(document).await;
+        }
+
+        let not_found = || Error::ApiError(ErrorKind::DocumentNotFound(locator.clone()));
+        match locator {
+            DocumentLocator::Identity { index } => {
+                let identity = self.get_identity(token).await?;
+                let document = identity.identity_details.identity_files().get(*index).ok_or_else(not_found)?;
+                self.open_document(document).await
+            },
+            DocumentLocator::DependencyIdentity { dependency_id, index } => {
+                let identity = self.get_dependency_identity(dependency_id, token).await?;
+                let document = identity.identity_details.identity_files().get(*index).ok_or_else(not_found)?;
+                self.open_document(document).await
+            },
+            DocumentLocator::Accreditation { accreditation_id, index } => {
+                let accreditations = self.get_accreditations(token).await?;
+                let document = accreditation_documents(&accreditations, accreditation_id)
+                    .and_then(|documents| documents.get(*index))
+                    .ok_or_else(not_found)?;
+                self.open_document(document).await
+            },
+        }
     }
 }
 
@@ -0,0 +1,335 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::risk::*;
+use crate::types::*;
+use json_api_client::types::*;
+
+/// Ownership threshold (in percent) used by the FinCEN beneficial ownership rule
+pub const FINCEN_OWNERSHIP_THRESHOLD: u32 = 25;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OwnershipNode {
+    /// Entity ID if the API provided one, otherwise a generated key derived from the parent node
+    pub key: String,
+    pub entity_id: Option<EntityId>,
+    pub entity_type: EntityType,
+    pub name: String,
+    pub business_type: Option<BusinessType>,
+    /// True if the identity details of a business node were available, so its own owners are part of the graph
+    pub resolved: bool,
+    /// Number of risk monitor matches, if the identity details of the node were available
+    pub risk_match_count: usize,
+    /// Union of the flags of all risk monitor matches
+    pub risk_flags: BTreeSet<RiskFlag>,
+    pub max_risk_score: Option<u8>,
+}
+
+impl OwnershipNode {
+    fn add_risk_matches<M: RiskMonitorMatch>(&mut self, matches: &[M]) {
+        self.risk_match_count = matches.len();
+        self.risk_flags = matches.iter().flat_map(|m| m.flags()).collect();
+        self.max_risk_score = matches.iter().map(|m| m.score()).max();
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "relation", rename_all = "snake_case")]
+pub enum OwnershipRelation {
+    BeneficialOwner { ownership_percent: Decimal },
+    ControlPerson { title: String },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct OwnershipEdge {
+    /// Key of the owning (or controlling) node
+    pub from: String,
+    /// Key of the business being owned (or controlled)
+    pub to: String,
+    #[serde(flatten)]
+    pub relation: OwnershipRelation,
+}
+
+/// Beneficial ownership graph of a business, including the owners of business owners if their identity is known
+#[derive(Debug, Clone)]
+pub struct OwnershipGraph {
+    pub root: String,
+    pub nodes: BTreeMap<String, OwnershipNode>,
+    pub edges: Vec<OwnershipEdge>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnershipPath {
+    /// Node keys from the root business (exclusive) down to the owner (inclusive)
+    pub nodes: Vec<String>,
+    pub effective_percent: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UltimateBeneficialOwner {
+    pub node: OwnershipNode,
+    /// Sum of the effective ownership over all paths
+    pub effective_percent: Decimal,
+    pub paths: Vec<OwnershipPath>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlPerson {
+    pub node: OwnershipNode,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeneficialOwnershipReport {
+    pub threshold_percent: Decimal,
+    /// Individuals owning at least the threshold, sorted by effective ownership
+    pub owners: Vec<UltimateBeneficialOwner>,
+    /// Business owners (or owners of an unknown type) at or above the threshold whose own owners are unknown
+    pub unresolved_owners: Vec<UltimateBeneficialOwner>,
+    /// Control persons of the root business
+    pub control_persons: Vec<ControlPerson>,
+}
+
+impl OwnershipGraph {
+    /// Builds the graph of `root`, descending into business owners found in `dependencies`.
+    ///
+    /// dependencies: identities fetched with `Client::get_dependency_identity`, keyed by their ID
+    pub fn build(root_id: &str, root: &BusinessIdentityDetails, dependencies: &HashMap<EntityId, DependencyIdentityResponse>) -> OwnershipGraph {
+        let mut graph = OwnershipGraph {
+            root: root_id.to_string(),
+            nodes: BTreeMap::new(),
+            edges: Vec::new(),
+        };
+        let mut root_node = OwnershipNode {
+            key: root_id.to_string(),
+            entity_id: Some(root_id.to_string()),
+            entity_type: EntityType::Business,
+            name: root.name.clone(),
+            business_type: Some(root.business_type.clone()),
+            resolved: true,
+            risk_match_count: 0,
+            risk_flags: BTreeSet::new(),
+            max_risk_score: None,
+        };
+        root_node.add_risk_matches(&root.risk_monitor_matches);
+        graph.nodes.insert(root_id.to_string(), root_node);
+
+        let mut expanded = HashSet::new();
+        graph.add_business(root_id, root, dependencies, &mut expanded);
+        graph
+    }
+
+    fn add_business(
+        &mut self,
+        key: &str,
+        details: &BusinessIdentityDetails,
+        dependencies: &HashMap<EntityId, DependencyIdentityResponse>,
+        expanded: &mut HashSet<String>,
+    ) {
+        if !expanded.insert(key.to_string()) {
+            return;
+        }
+
+        for (i, person) in details.control_persons.iter().enumerate() {
+            let person_key = person.id.clone().unwrap_or_else(|| format!("{}/control/{}", key, i));
+            let node = self.nodes.entry(person_key.clone()).or_insert_with(|| OwnershipNode {
+                key: person_key.clone(),
+                entity_id: person.id.clone(),
+                entity_type: EntityType::Individual,
+                name: individual_name(&person.profile),
+                business_type: None,
+                resolved: false,
+                risk_match_count: 0,
+                risk_flags: BTreeSet::new(),
+                max_risk_score: None,
+            });
+            if let Some(IdentityDetails::Individual(details)) = person.id.as_ref().and_then(|id| dependencies.get(id)).map(|d| &d.identity_details) {
+                node.add_risk_matches(&details.risk_monitor_matches);
+            }
+            self.edges.push(OwnershipEdge {
+                from: person_key,
+                to: key.to_string(),
+                relation: OwnershipRelation::ControlPerson { title: person.title.clone() },
+            });
+        }
+
+        for (i, owner) in details.direct_beneficial_owners.iter().enumerate() {
+            let owner_key = owner.id.clone().unwrap_or_else(|| format!("{}/owner/{}", key, i));
+            let dependency = owner.id.as_ref().and_then(|id| dependencies.get(id)).map(|d| &d.identity_details);
+            let owner_details = dependency.and_then(|details| details.as_business());
+
+            let (name, business_type) = match &owner.profile {
+                Profile::Business(business) => (business.name.clone(), Some(business.business_type.clone())),
+                Profile::Individual(individual) => (individual_name(individual), None),
+            };
+            let node = self.nodes.entry(owner_key.clone()).or_insert_with(|| OwnershipNode {
+                key: owner_key.clone(),
+                entity_id: owner.id.clone(),
+                entity_type: owner.reference_type.clone(),
+                name,
+                business_type,
+                resolved: false,
+                risk_match_count: 0,
+                risk_flags: BTreeSet::new(),
+                max_risk_score: None,
+            });
+            match (&owner.reference_type, dependency) {
+                (EntityType::Business, Some(IdentityDetails::Business(details))) => {
+                    node.resolved = true;
+                    node.add_risk_matches(&details.risk_monitor_matches);
+                },
+                (EntityType::Individual, Some(IdentityDetails::Individual(details))) => node.add_risk_matches(&details.risk_monitor_matches),
+                _ => {},
+            }
+
+            self.edges.push(OwnershipEdge {
+                from: owner_key.clone(),
+                to: key.to_string(),
+                relation: OwnershipRelation::BeneficialOwner {
+                    ownership_percent: owner.ownership_percent,
+                },
+            });
+
+            if owner.reference_type == EntityType::Business {
+                if let Some(owner_details) = owner_details {
+                    self.add_business(&owner_key, owner_details, dependencies, expanded);
+                }
+            }
+        }
+    }
+
+    /// Direct beneficial owner edges of the node with the given key
+    pub fn owners_of<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (&'a OwnershipNode, Decimal)> + 'a {
+        self.edges.iter().filter(move |edge| edge.to == key).filter_map(move |edge| match &edge.relation {
+            OwnershipRelation::BeneficialOwner { ownership_percent } => self.nodes.get(&edge.from).map(|node| (node, *ownership_percent)),
+            OwnershipRelation::ControlPerson { .. } => None,
+        })
+    }
+
+    /// Computes the ultimate beneficial owners of the root business.
+    ///
+    /// Ownership is multiplied through layered business owners and summed when an individual is reached via multiple paths.
+    /// threshold_percent: minimum effective ownership to report, e.g. `FINCEN_OWNERSHIP_THRESHOLD`
+    pub fn ultimate_beneficial_owners(&self, threshold_percent: Decimal) -> BeneficialOwnershipReport {
+        let mut paths: BTreeMap<String, Vec<OwnershipPath>> = BTreeMap::new();
+        let mut stack = vec![self.root.clone()];
+        self.collect_paths(&self.root, Decimal::from(100), &mut stack, &mut paths);
+
+        let mut owners = Vec::new();
+        let mut unresolved_owners = Vec::new();
+        for (key, paths) in paths {
+            let node = &self.nodes[&key];
+            let effective_percent = paths.iter().map(|p| p.effective_percent).fold(Decimal::ZERO, |acc, p| acc + p);
+            if effective_percent < threshold_percent {
+                continue;
+            }
+            let owner = UltimateBeneficialOwner {
+                node: node.clone(),
+                effective_percent,
+                paths,
+            };
+            match node.entity_type {
+                EntityType::Individual => owners.push(owner),
+                EntityType::Business | EntityType::Unknown(_) => unresolved_owners.push(owner),
+            }
+        }
+        owners.sort_by(|a, b| b.effective_percent.cmp(&a.effective_percent).then_with(|| a.node.name.cmp(&b.node.name)));
+        unresolved_owners.sort_by(|a, b| b.effective_percent.cmp(&a.effective_percent).then_with(|| a.node.name.cmp(&b.node.name)));
+
+        let control_persons = self
+            .edges
+            .iter()
+            .filter(|edge| edge.to == self.root)
+            .filter_map(|edge| match &edge.relation {
+                OwnershipRelation::ControlPerson { title } => Some(ControlPerson {
+                    node: self.nodes[&edge.from].clone(),
+                    title: title.clone(),
+                }),
+                OwnershipRelation::BeneficialOwner { .. } => None,
+            })
+            .collect();
+
+        BeneficialOwnershipReport {
+            threshold_percent,
+            owners,
+            unresolved_owners,
+            control_persons,
+        }
+    }
+
+    fn collect_paths(&self, key: &str, percent: Decimal, stack: &mut Vec<String>, paths: &mut BTreeMap<String, Vec<OwnershipPath>>) {
+        for (owner, ownership_percent) in self.owners_of(key) {
+            // Circular ownership can't increase anyone's stake, so the cycle is cut here
+            if stack.contains(&owner.key) {
+                continue;
+            }
+            let effective_percent = percent * ownership_percent / Decimal::from(100);
+            stack.push(owner.key.clone());
+
+            if owner.entity_type == EntityType::Business && owner.resolved {
+                self.collect_paths(&owner.key, effective_percent, stack, paths);
+            } else {
+                paths.entry(owner.key.clone()).or_default().push(OwnershipPath {
+                    nodes: stack[1..].to_vec(),
+                    effective_percent,
+                });
+            }
+
+            stack.pop();
+        }
+    }
+}
+
+fn individual_name(profile: &IndividualProfile) -> String {
+    format!("{} {}", profile.first_name, profile.last_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    #[test]
+    fn layered_owners_are_multiplied_and_aggregated() {
+        let root: BusinessIdentityDetails = serde_json::from_value(business_identity(
+            "Root Corp",
+            vec![
+                individual_owner("alice", "Alice", "Smith", 20),
+                business_owner("holding", "Holding LLC", 50),
+                individual_owner("bob", "Bob", "Jones", 30),
+            ],
+            vec![control_person("carol", "Carol", "White", "CEO")],
+        ))
+        .unwrap();
+        let holding = dependency_identity(
+            "holding",
+            "business",
+            business_identity("Holding LLC", vec![individual_owner("alice", "Alice", "Smith", 60), individual_owner("dave", "Dave", "Brown", 40)], vec![]),
+        );
+        let dependencies = HashMap::from([("holding".to_string(), serde_json::from_value(holding).unwrap())]);
+
+        let graph = OwnershipGraph::build("root", &root, &dependencies);
+        let report = graph.ultimate_beneficial_owners(Decimal::from(FINCEN_OWNERSHIP_THRESHOLD));
+
+        let owners: Vec<(&str, Decimal)> = report.owners.iter().map(|o| (o.node.key.as_str(), o.effective_percent)).collect();
+        assert_eq!(owners, vec![("alice", Decimal::from(50)), ("bob", Decimal::from(30))]);
+        assert_eq!(report.owners[0].paths.len(), 2);
+        assert_eq!(report.owners[0].paths[1].nodes, vec!["holding".to_string(), "alice".to_string()]);
+        assert!(report.unresolved_owners.is_empty());
+        assert_eq!(report.control_persons.len(), 1);
+        assert_eq!(report.control_persons[0].title, "CEO");
+    }
+
+    #[test]
+    fn unknown_business_owners_are_reported_as_unresolved() {
+        let root: BusinessIdentityDetails =
+            serde_json::from_value(business_identity("Root Corp", vec![business_owner("holding", "Holding LLC", 80)], vec![])).unwrap();
+
+        let graph = OwnershipGraph::build("root", &root, &HashMap::new());
+        let report = graph.ultimate_beneficial_owners(Decimal::from(FINCEN_OWNERSHIP_THRESHOLD));
+
+        assert!(report.owners.is_empty());
+        assert_eq!(report.unresolved_owners.len(), 1);
+        assert_eq!(report.unresolved_owners[0].effective_percent, Decimal::from(80));
+    }
+}
@@ -0,0 +1,127 @@
+use serde::Serialize;
+use std::fmt::Write;
+
+use crate::ownership::*;
+use crate::types::*;
+
+/// Version of the JSON document produced by `OwnershipGraph::to_json`, bumped on incompatible changes
+pub const OWNERSHIP_GRAPH_FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct OwnershipGraphDocument<'a> {
+    format_version: u32,
+    root: &'a str,
+    nodes: Vec<&'a OwnershipNode>,
+    edges: Vec<&'a OwnershipEdge>,
+}
+
+impl OwnershipGraph {
+    /// Renders the graph in Graphviz DOT format. Owners point to the business they own or control.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph ownership {\n    rankdir=BT;\n    node [fontname=\"Helvetica\"];\n");
+
+        for node in self.nodes.values() {
+            let mut label = node.name.clone();
+            if let Some(business_type) = &node.business_type {
+                label.push_str(&format!("\n{}", business_type));
+            }
+            if !node.risk_flags.is_empty() {
+                let flags: Vec<String> = node.risk_flags.iter().map(|flag| flag.to_string()).collect();
+                label.push_str(&format!("\nrisk: {}", flags.join(", ")));
+            }
+
+            let shape = match node.entity_type {
+                EntityType::Business => "box",
+                EntityType::Individual => "ellipse",
+                EntityType::Unknown(_) => "diamond",
+            };
+            let mut attributes = format!("label=\"{}\", shape={}", escape(&label), shape);
+            if node.key == self.root {
+                attributes.push_str(", penwidth=2");
+            }
+            if node.risk_match_count > 0 {
+                attributes.push_str(", color=red");
+            }
+            if node.entity_type == EntityType::Business && !node.resolved {
+                attributes.push_str(", style=dashed");
+            }
+            let _ = writeln!(dot, "    \"{}\" [{}];", escape(&node.key), attributes);
+        }
+
+        for edge in self.sorted_edges() {
+            let attributes = match &edge.relation {
+                OwnershipRelation::BeneficialOwner { ownership_percent } => format!("label=\"{}%\"", ownership_percent),
+                OwnershipRelation::ControlPerson { title } => format!("label=\"{}\", style=dotted", escape(title)),
+            };
+            let _ = writeln!(dot, "    \"{}\" -> \"{}\" [{}];", escape(&edge.from), escape(&edge.to), attributes);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Serializes the graph as a JSON document with nodes and edges in a stable order
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let document = OwnershipGraphDocument {
+            format_version: OWNERSHIP_GRAPH_FORMAT_VERSION,
+            root: &self.root,
+            nodes: self.nodes.values().collect(),
+            edges: self.sorted_edges(),
+        };
+        serde_json::to_string_pretty(&document)
+    }
+
+    fn sorted_edges(&self) -> Vec<&OwnershipEdge> {
+        let mut edges: Vec<&OwnershipEdge> = self.edges.iter().collect();
+        // Sorted by target and then owner ID so the output doesn't depend on the API's order. The sort is stable,
+        // so an entity that is both owner and control person of the same business keeps the edge order of the graph.
+        edges.sort_by(|a, b| a.to.cmp(&b.to).then_with(|| a.from.cmp(&b.from)));
+        edges
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use std::collections::HashMap;
+
+    fn graph() -> OwnershipGraph {
+        let root: BusinessIdentityDetails = serde_json::from_value(business_identity(
+            "Root \"Corp\"",
+            vec![individual_owner("alice", "Alice", "Smith", 60), business_owner("holding", "Holding LLC", 40)],
+            vec![control_person("carol", "Carol", "White", "CEO")],
+        ))
+        .unwrap();
+        OwnershipGraph::build("root", &root, &HashMap::new())
+    }
+
+    #[test]
+    fn dot_contains_nodes_and_labelled_edges() {
+        let dot = graph().to_dot();
+
+        assert!(dot.starts_with("digraph ownership {"));
+        assert!(dot.contains("\"root\" [label=\"Root \\\"Corp\\\"\\nC Corporation\", shape=box, penwidth=2];"));
+        assert!(dot.contains("\"holding\" [label=\"Holding LLC\\nC Corporation\", shape=box, style=dashed];"));
+        assert!(dot.contains("\"alice\" -> \"root\" [label=\"60%\"];"));
+        assert!(dot.contains("\"carol\" -> \"root\" [label=\"CEO\", style=dotted];"));
+    }
+
+    #[test]
+    fn json_is_stable() {
+        let graph = graph();
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["format_version"], OWNERSHIP_GRAPH_FORMAT_VERSION);
+        let nodes: Vec<&str> = json["nodes"].as_array().unwrap().iter().map(|n| n["key"].as_str().unwrap()).collect();
+        assert_eq!(nodes, vec!["alice", "carol", "holding", "root"]);
+        assert_eq!(json["edges"][0]["relation"], "beneficial_owner");
+        assert_eq!(json["edges"][1]["relation"], "control_person");
+        assert_eq!(json["edges"][1]["title"], "CEO");
+        assert_eq!(graph.to_json().unwrap(), graph.to_json().unwrap());
+    }
+}
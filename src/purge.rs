@@ -0,0 +1,397 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error as StdError;
+use std::path::PathBuf;
+use std::sync::Arc;
+use time::Duration;
+
+use crate::access::*;
+use crate::clock::*;
+use crate::datetime::lenient;
+use crate::json_file::*;
+use crate::types::*;
+use crate::{Error, Result};
+use json_api_client::types::*;
+
+/// Error of a purge callback, e.g. from the application's database
+pub type PurgeError = Box<dyn StdError + Send + Sync>;
+
+/// Deletes the data of a subject from the application's storage
+pub type PurgeCallback = Box<dyn FnMut(&str) -> std::result::Result<(), PurgeError> + Send>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SubjectAccess {
+    pub state: AccessState,
+    /// When the state was recorded, used as the revocation time if the API doesn't report it
+    #[serde(with = "lenient")]
+    pub known_since: DateTime,
+}
+
+/// Storage of the purge schedule, keyed by subject ID, so pending purges survive a restart
+pub trait PurgeStore: Send {
+    fn load(&self) -> Result<BTreeMap<EntityId, SubjectAccess>>;
+    fn save(&mut self, subjects: &BTreeMap<EntityId, SubjectAccess>) -> Result<()>;
+}
+
+/// Keeps nothing, the schedule only lives in the `PurgeScheduler`
+#[derive(Debug, Default)]
+pub struct InMemoryPurgeStore;
+
+impl PurgeStore for InMemoryPurgeStore {
+    fn load(&self) -> Result<BTreeMap<EntityId, SubjectAccess>> {
+        Ok(BTreeMap::new())
+    }
+
+    fn save(&mut self, _subjects: &BTreeMap<EntityId, SubjectAccess>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Keeps the schedule in one JSON file, rewritten on every change
+#[derive(Debug)]
+pub struct JsonFilePurgeStore {
+    path: PathBuf,
+}
+
+impl JsonFilePurgeStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> JsonFilePurgeStore {
+        JsonFilePurgeStore { path: path.into() }
+    }
+}
+
+impl PurgeStore for JsonFilePurgeStore {
+    /// Starts empty if the file doesn't exist
+    fn load(&self) -> Result<BTreeMap<EntityId, SubjectAccess>> {
+        Ok(read_json_file(&self.path)?.unwrap_or_default())
+    }
+
+    fn save(&mut self, subjects: &BTreeMap<EntityId, SubjectAccess>) -> Result<()> {
+        write_json_file(&self.path, subjects)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CallbackOutcome {
+    pub callback: String,
+    /// None if the callback succeeded, or wasn't called in a dry run
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PurgeItem {
+    pub subject_id: EntityId,
+    #[serde(with = "lenient")]
+    pub deadline: DateTime,
+    pub callbacks: Vec<CallbackOutcome>,
+}
+
+impl PurgeItem {
+    pub fn succeeded(&self) -> bool {
+        self.callbacks.iter().all(|outcome| outcome.error.is_none())
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PurgeReport {
+    pub dry_run: bool,
+    /// Subjects whose deadline has passed
+    pub due: Vec<PurgeItem>,
+    /// Subjects with a deadline in the future, soonest first
+    pub upcoming: Vec<(EntityId, DateTime)>,
+}
+
+/// Tracks when the data of each subject must be deleted, from responses and `AccessRevocationScheduled` webhooks,
+/// and runs the registered purge callbacks once the deadline passes
+pub struct PurgeScheduler {
+    subjects: BTreeMap<EntityId, SubjectAccess>,
+    callbacks: Vec<(String, PurgeCallback)>,
+    grace_period: Duration,
+    clock: Arc<dyn Clock>,
+    store: Box<dyn PurgeStore>,
+}
+
+impl Default for PurgeScheduler {
+    fn default() -> Self {
+        PurgeScheduler::new()
+    }
+}
+
+impl PurgeScheduler {
+    pub fn new() -> PurgeScheduler {
+        PurgeScheduler {
+            subjects: BTreeMap::new(),
+            callbacks: Vec::new(),
+            grace_period: Duration::ZERO,
+            clock: Arc::new(SystemClock),
+            store: Box::new(InMemoryPurgeStore),
+        }
+    }
+
+    /// Loads the schedule from `store`, which keeps it from then on
+    pub fn with_store<S: PurgeStore + 'static>(mut self, store: S) -> Result<PurgeScheduler> {
+        self.subjects = store.load()?;
+        self.store = Box::new(store);
+        Ok(self)
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> PurgeScheduler {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Time allowed after access ends before the data is purged, none by default
+    pub fn with_grace_period(mut self, grace_period: Duration) -> PurgeScheduler {
+        self.grace_period = grace_period;
+        self
+    }
+
+    /// Callbacks are run in registration order, `name` identifies them in reports
+    pub fn with_purge_callback<F>(mut self, name: &str, callback: F) -> PurgeScheduler
+    where
+        F: FnMut(&str) -> std::result::Result<(), PurgeError> + Send + 'static,
+    {
+        self.callbacks.push((name.to_string(), Box::new(callback)));
+        self
+    }
+
+    /// Records the access state of a subject. A state never replaces a stronger one, so e.g. an active state doesn't
+    /// cancel a revocation known from a webhook. Use `record_response` to cancel a purge once access is granted again.
+    pub fn record(&mut self, subject_id: &str, state: AccessState) -> Result<()> {
+        self.update(subject_id, state, false)
+    }
+
+    /// Records the access state of a profile or identity response. Dependency identities are recorded under their own ID.
+    /// A revoked token can't fetch anything, so a response received after the known revocation took effect shows
+    /// that access was granted again, and its state replaces the revocation, cancelling a pending purge.
+    pub fn record_response<R: AccessInfo>(&mut self, subject_id: &str, response: &R) -> Result<()> {
+        let state = response.access_state(&self.clock);
+        self.update(subject_id, state, true)
+    }
+
+    fn update(&mut self, subject_id: &str, state: AccessState, regranted_after_revocation: bool) -> Result<()> {
+        let now = self.clock.now();
+        let replace = match self.subjects.get(subject_id) {
+            None => true,
+            Some(known) if known.state == state => false,
+            Some(known) => state.replaces(&known.state) || (regranted_after_revocation && known.state.is_revoked_at(now)),
+        };
+        if replace {
+            self.subjects.insert(subject_id.to_string(), SubjectAccess { state, known_since: now });
+            self.store.save(&self.subjects)?;
+        }
+        Ok(())
+    }
+
+    /// Marks the webhook's entity as scheduled for revocation. The deadline is unknown until a response reports `access_expires_at`.
+    pub fn record_webhook(&mut self, webhook: &WebhookData) -> Result<()> {
+        if webhook.event != EventType::AccessRevocationScheduled {
+            return Ok(());
+        }
+        let known = self.subjects.get(&webhook.entity.id).map(|access| &access.state);
+        if matches!(known, None | Some(AccessState::Active)) {
+            self.record(&webhook.entity.id, AccessState::RevocationScheduled { until: None, by: None })?;
+        }
+        Ok(())
+    }
+
+    /// When the data of the subject must be purged, None if access isn't ending or its end isn't known yet
+    pub fn deadline(&self, subject_id: &str) -> Option<DateTime> {
+        let access = self.subjects.get(subject_id)?;
+        let ends_at = match &access.state {
+            AccessState::Active | AccessState::RevocationScheduled { until: None, .. } => return None,
+            AccessState::RevocationScheduled { until: Some(until), .. } => *until,
+            AccessState::Revoked { at, .. } => at.unwrap_or(access.known_since),
+        };
+        Some(ends_at.saturating_add(self.grace_period))
+    }
+
+    /// Reports what `run` would purge, without calling the callbacks
+    pub fn dry_run(&self) -> PurgeReport {
+        let now = self.clock.now();
+        let mut report = self.plan(now, true);
+        for item in &mut report.due {
+            item.callbacks = self
+                .callbacks
+                .iter()
+                .map(|(name, _)| CallbackOutcome {
+                    callback: name.clone(),
+                    error: None,
+                })
+                .collect();
+        }
+        report
+    }
+
+    /// Runs every callback for each subject whose deadline has passed. Subjects are forgotten once all callbacks succeed,
+    /// otherwise they are retried on the next run. Fails without a registered callback, as nothing would be purged.
+    pub fn run(&mut self) -> Result<PurgeReport> {
+        if self.callbacks.is_empty() {
+            return Err(Error::ConfigError("no purge callback is registered".to_string()));
+        }
+        let now = self.clock.now();
+        let mut report = self.plan(now, false);
+        for item in &mut report.due {
+            for (name, callback) in &mut self.callbacks {
+                item.callbacks.push(CallbackOutcome {
+                    callback: name.clone(),
+                    error: callback(&item.subject_id).err().map(|e| e.to_string()),
+                });
+            }
+            if item.succeeded() {
+                self.subjects.remove(&item.subject_id);
+            }
+        }
+        if report.due.iter().any(PurgeItem::succeeded) {
+            self.store.save(&self.subjects)?;
+        }
+        Ok(report)
+    }
+
+    fn plan(&self, now: DateTime, dry_run: bool) -> PurgeReport {
+        let mut due = Vec::new();
+        let mut upcoming = Vec::new();
+        for subject_id in self.subjects.keys() {
+            match self.deadline(subject_id) {
+                Some(deadline) if deadline <= now => due.push(PurgeItem {
+                    subject_id: subject_id.clone(),
+                    deadline,
+                    callbacks: Vec::new(),
+                }),
+                Some(deadline) => upcoming.push((subject_id.clone(), deadline)),
+                None => {},
+            }
+        }
+        due.sort_by_key(|item| item.deadline);
+        upcoming.sort_by_key(|(_, deadline)| *deadline);
+        PurgeReport { dry_run, due, upcoming }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use time::macros::datetime;
+
+    fn response(expires_at: Option<DateTime>) -> DependencyIdentityResponse {
+        let response = dependency_identity("subject", "individual", individual_identity("Alice", "Smith"));
+        serde_json::from_value(with(response, json!({ "access_expires_at": expires_at.map(DateTime::unix_timestamp) }))).unwrap()
+    }
+
+    fn scheduler(now: DateTime, purged: Arc<Mutex<Vec<String>>>) -> PurgeScheduler {
+        PurgeScheduler::new()
+            .with_clock(FixedClock(now))
+            .with_grace_period(Duration::days(30))
+            .with_purge_callback("documents", move |subject_id| {
+                purged.lock().unwrap().push(subject_id.to_string());
+                Ok(())
+            })
+    }
+
+    fn revoked(at: DateTime) -> AccessState {
+        AccessState::Revoked {
+            at: Some(at),
+            by: Some(RevokeType::Subject),
+        }
+    }
+
+    #[test]
+    fn dry_run_reports_without_purging() {
+        let purged = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = scheduler(datetime!(2023-03-01 00:00 UTC), purged.clone());
+        scheduler.record("revoked", revoked(datetime!(2023-01-10 12:00 UTC))).unwrap();
+        scheduler
+            .record(
+                "scheduled",
+                AccessState::RevocationScheduled {
+                    until: Some(datetime!(2023-03-10 00:00 UTC)),
+                    by: None,
+                },
+            )
+            .unwrap();
+        scheduler.record("active", AccessState::Active).unwrap();
+
+        let report = scheduler.dry_run();
+        assert!(report.dry_run);
+        assert_eq!(report.due[0].subject_id, "revoked");
+        assert_eq!(report.due[0].deadline, datetime!(2023-02-09 12:00 UTC));
+        assert_eq!(report.upcoming, vec![("scheduled".to_string(), datetime!(2023-04-09 00:00 UTC))]);
+        assert!(purged.lock().unwrap().is_empty());
+
+        let report = scheduler.run().unwrap();
+        assert!(report.due[0].succeeded());
+        assert_eq!(*purged.lock().unwrap(), vec!["revoked".to_string()]);
+        assert!(scheduler.run().unwrap().due.is_empty());
+    }
+
+    #[test]
+    fn failed_purges_are_retried() {
+        let mut scheduler = PurgeScheduler::new()
+            .with_clock(FixedClock(datetime!(2023-03-01 00:00 UTC)))
+            .with_purge_callback("failing", |_| Err("storage unavailable".into()));
+        scheduler.record("subject", revoked(datetime!(2023-01-10 12:00 UTC))).unwrap();
+
+        let report = scheduler.run().unwrap();
+        assert_eq!(report.due.len(), 1);
+        assert_eq!(report.due[0].callbacks[0].error.as_deref(), Some("storage unavailable"));
+        assert_eq!(scheduler.run().unwrap().due.len(), 1);
+    }
+
+    #[test]
+    fn responses_without_access_fields_keep_a_pending_revocation() {
+        let purged = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = scheduler(datetime!(2023-03-01 00:00 UTC), purged);
+        scheduler.record("subject", AccessState::RevocationScheduled { until: None, by: None }).unwrap();
+        scheduler.record_response("subject", &response(None)).unwrap();
+        scheduler.record("subject", AccessState::Active).unwrap();
+        assert_eq!(scheduler.subjects["subject"].state, AccessState::RevocationScheduled { until: None, by: None });
+
+        scheduler.record_response("subject", &response(Some(datetime!(2023-03-10 00:00 UTC)))).unwrap();
+        assert_eq!(scheduler.deadline("subject"), Some(datetime!(2023-04-09 00:00 UTC)));
+        scheduler.record_response("subject", &response(None)).unwrap();
+        assert_eq!(scheduler.deadline("subject"), Some(datetime!(2023-04-09 00:00 UTC)));
+    }
+
+    #[test]
+    fn responses_after_the_revocation_cancel_the_purge() {
+        let purged = Arc::new(Mutex::new(Vec::new()));
+        let mut scheduler = scheduler(datetime!(2023-03-01 00:00 UTC), purged);
+        scheduler.record("returning", revoked(datetime!(2023-01-10 12:00 UTC))).unwrap();
+        scheduler.record("returning", AccessState::Active).unwrap();
+        assert!(scheduler.deadline("returning").is_some());
+
+        scheduler.record_response("returning", &response(None)).unwrap();
+        assert_eq!(scheduler.deadline("returning"), None);
+    }
+
+    #[test]
+    fn run_without_callbacks_keeps_the_schedule() {
+        let mut scheduler = PurgeScheduler::new().with_clock(FixedClock(datetime!(2023-03-01 00:00 UTC)));
+        scheduler.record("revoked", revoked(datetime!(2023-01-10 12:00 UTC))).unwrap();
+
+        assert!(matches!(scheduler.run(), Err(Error::ConfigError(_))));
+        assert_eq!(scheduler.dry_run().due.len(), 1);
+    }
+
+    #[test]
+    fn json_file_store_keeps_the_schedule_across_restarts() {
+        let path = std::env::temp_dir().join(format!("purge-schedule-{}.json", std::process::id()));
+        let now = datetime!(2023-03-01 00:00 UTC);
+        let purged = Arc::new(Mutex::new(Vec::new()));
+
+        let mut first = scheduler(now, purged.clone()).with_store(JsonFilePurgeStore::new(&path)).unwrap();
+        first.record("revoked", revoked(datetime!(2023-01-10 12:00 UTC))).unwrap();
+        first.record("webhook", AccessState::RevocationScheduled { until: None, by: None }).unwrap();
+
+        let mut restarted = scheduler(now, purged.clone()).with_store(JsonFilePurgeStore::new(&path)).unwrap();
+        assert_eq!(restarted.deadline("revoked"), Some(datetime!(2023-02-09 12:00 UTC)));
+        assert_eq!(restarted.run().unwrap().due.len(), 1);
+
+        let restarted = scheduler(now, purged).with_store(JsonFilePurgeStore::new(&path)).unwrap();
+        assert_eq!(restarted.deadline("revoked"), None);
+        assert!(restarted.subjects.contains_key("webhook"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::secret::Secret;
+use crate::types::{join_path, payload_error_path};
+use crate::{Error, Result};
+
+/// A parsed response together with the JSON it was parsed from
+#[derive(Debug)]
+pub struct WithRaw<T> {
+    pub value: T,
+    /// The response body as received from the API, redacted in `Debug`
+    pub raw: Secret<Value>,
+}
+
+impl<T> WithRaw<T> {
+    pub fn into_value(self) -> T {
+        self.value
+    }
+}
+
+/// Parses `raw` into `T`, keeping the body and the path of the failing field on error
+pub(crate) fn parse_response<T: DeserializeOwned>(raw: Value) -> Result<WithRaw<T>> {
+    match serde_path_to_error::deserialize(&raw) {
+        Ok(value) => Ok(WithRaw { value, raw: Secret::new(raw) }),
+        Err(e) => {
+            // Failures inside a tagged payload are reported at the struct holding it, with the rest of the path in the message
+            let path = match payload_error_path(&e.inner().to_string()) {
+                Some(payload_path) => join_path(&e.path().to_string(), payload_path),
+                None => e.path().to_string(),
+            };
+            Err(Error::ParseError {
+                path,
+                source: e.into_inner(),
+                raw: Box::new(Secret::new(raw)),
+            })
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::types::*;
+    use serde_json::json;
+
+    fn holding(owner_overrides: Value) -> Value {
+        let owner = with(individual_owner("alice", "Alice", "Smith", 100), owner_overrides);
+        dependency_identity("holding", "business", business_identity("Holding LLC", vec![owner], vec![]))
+    }
+
+    fn error_path(raw: Value) -> String {
+        match parse_response::<DependencyIdentityResponse>(raw).unwrap_err() {
+            Error::ParseError { path, .. } => path,
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn parse_error_keeps_path_and_body() {
+        let raw = with(holding(json!({})), json!({ "access_expires_at": true }));
+
+        let error = parse_response::<DependencyIdentityResponse>(raw.clone()).unwrap_err();
+
+        assert!(!format!("{:?}", error).contains("Holding"), "{:?}", error);
+        assert_eq!(error.to_string(), "Failed to parse response at `access_expires_at`");
+        match error {
+            Error::ParseError { path, raw: body, .. } => {
+                assert_eq!(path, "access_expires_at");
+                assert_eq!(body.expose(), &raw);
+            },
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn parsed_responses_keep_the_body_redacted() {
+        let raw = holding(json!({}));
+
+        let parsed = parse_response::<DependencyIdentityResponse>(raw.clone()).unwrap();
+
+        assert_eq!(parsed.raw.expose(), &raw);
+        assert_eq!(format!("{:?}", parsed.raw), "[REDACTED]");
+    }
+
+    #[test]
+    fn earlier_payload_failures_dont_affect_later_errors() {
+        let broken = holding(json!({ "ownership_percent": "all" }));
+        assert!(serde_json::from_value::<DependencyIdentityResponse>(broken).is_err());
+
+        assert_eq!(error_path(with(holding(json!({})), json!({ "access_expires_at": true }))), "access_expires_at");
+    }
+
+    #[test]
+    fn payload_errors_name_the_field() {
+        let broken = holding(json!({ "ownership_percent": "all" }));
+
+        assert_eq!(error_path(broken), "identity_details.direct_beneficial_owners[0].ownership_percent");
+    }
+
+    #[test]
+    fn invalid_profiles_are_named() {
+        let owner = json!({ "id": "alice", "type": "individual", "ownership_percent": 100, "profile": { "first_name": "Alice" } });
+        let broken = dependency_identity("holding", "business", business_identity("Holding LLC", vec![owner], vec![]));
+
+        assert_eq!(error_path(broken), "identity_details.direct_beneficial_owners[0].profile");
+    }
+
+    #[test]
+    fn profile_field_errors_name_the_profile_field() {
+        let broken = holding(json!({ "profile": { "last_name": 7 } }));
+
+        assert_eq!(error_path(broken), "identity_details.direct_beneficial_owners[0].profile.last_name");
+    }
+}
@@ -0,0 +1,248 @@
+use std::fmt;
+use time::Duration;
+
+use crate::accreditation::*;
+use crate::types::*;
+use json_api_client::types::*;
+
+/// Look-back window commonly used for Rule 506(c) verifications
+pub const DEFAULT_RELIANCE_WINDOW_DAYS: i64 = 90;
+
+/// Decides whether an accreditation verification is recent enough to rely on for a Rule 506(c) offering
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReliancePolicy {
+    /// How long a verification can be relied on, counted from `certified_at`
+    pub look_back: Duration,
+}
+
+impl Default for ReliancePolicy {
+    fn default() -> Self {
+        ReliancePolicy {
+            look_back: Duration::days(DEFAULT_RELIANCE_WINDOW_DAYS),
+        }
+    }
+}
+
+/// A verification that can be relied on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reliance {
+    pub accreditation_id: String,
+    pub certified_at: DateTime,
+    /// End of the look-back window, or the accreditation's expiry if that is earlier
+    pub ends_at: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelianceFailure {
+    /// The subject has no accreditation
+    NoAccreditation,
+    /// Pending review or awaiting a third party evaluator
+    Pending(AccreditationStatus),
+    Rejected,
+    /// A status not known to this version of the crate
+    UnknownStatus(String),
+    /// The accreditation was never certified
+    NoCertificationDate,
+    /// Certified after the offering date
+    CertifiedAfterOffering {
+        certified_at: DateTime,
+    },
+    Expired {
+        at: Option<DateTime>,
+    },
+    /// The look-back window ended before the offering date
+    WindowEnded {
+        certified_at: DateTime,
+        ended_at: DateTime,
+    },
+}
+
+impl fmt::Display for RelianceFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            RelianceFailure::NoAccreditation => "no accreditation".to_string(),
+            RelianceFailure::Pending(AccreditationStatus::ThirdPartyPending) => "accreditation awaiting a third party evaluator".to_string(),
+            RelianceFailure::Pending(_) => "accreditation pending review".to_string(),
+            RelianceFailure::Rejected => "accreditation rejected".to_string(),
+            RelianceFailure::UnknownStatus(status) => format!("accreditation has unknown status '{}'", status),
+            RelianceFailure::NoCertificationDate => "accreditation has no certification date".to_string(),
+            RelianceFailure::CertifiedAfterOffering { certified_at } => format!("certified after the offering date, at {}", certified_at),
+            RelianceFailure::Expired { at: Some(at) } => format!("accreditation expired at {}", at),
+            RelianceFailure::Expired { at: None } => "accreditation expired".to_string(),
+            RelianceFailure::WindowEnded { certified_at, ended_at } => format!("certified at {}, reliance ended at {}", certified_at, ended_at),
+        };
+        write!(f, "{}", str)
+    }
+}
+
+impl ReliancePolicy {
+    pub fn with_look_back_days(days: i64) -> ReliancePolicy {
+        ReliancePolicy {
+            look_back: Duration::days(days),
+        }
+    }
+
+    /// Checks a single accreditation for an offering on `offering_date`
+    pub fn check(&self, accreditation: &dyn AccreditationRecord, offering_date: DateTime) -> Result<Reliance, RelianceFailure> {
+        match accreditation.status_at(offering_date) {
+            AccreditationStatus::Current => {},
+            AccreditationStatus::Expired => {
+                return Err(RelianceFailure::Expired {
+                    at: accreditation.expires_at(),
+                })
+            },
+            status @ (AccreditationStatus::Pending | AccreditationStatus::ThirdPartyPending) => return Err(RelianceFailure::Pending(status)),
+            AccreditationStatus::Rejected => return Err(RelianceFailure::Rejected),
+            AccreditationStatus::Unknown(status) => return Err(RelianceFailure::UnknownStatus(status)),
+        }
+
+        let certified_at = accreditation.certified_at().ok_or(RelianceFailure::NoCertificationDate)?;
+        if certified_at > offering_date {
+            return Err(RelianceFailure::CertifiedAfterOffering { certified_at });
+        }
+        let window_end = certified_at.saturating_add(self.look_back);
+        let ends_at = accreditation.expires_at().map(|expires_at| expires_at.min(window_end)).unwrap_or(window_end);
+        if ends_at <= offering_date {
+            return Err(RelianceFailure::WindowEnded {
+                certified_at,
+                ended_at: ends_at,
+            });
+        }
+
+        Ok(Reliance {
+            accreditation_id: accreditation.id().to_string(),
+            certified_at,
+            ends_at,
+        })
+    }
+
+    /// Passes if any of the subject's accreditations can be relied on for `offering_date`, returning the most recently
+    /// certified of those. Otherwise fails with the reason of the most recently created accreditation.
+    pub fn check_response(&self, accreditations: &AccreditationsResponse, offering_date: DateTime) -> Result<Reliance, RelianceFailure> {
+        let mut reliance: Option<Reliance> = None;
+        let mut failure: Option<(DateTime, RelianceFailure)> = None;
+        for record in accreditations.accreditations.records() {
+            match self.check(record, offering_date) {
+                Ok(found) if reliance.as_ref().map(|best| found.certified_at > best.certified_at).unwrap_or(true) => reliance = Some(found),
+                Ok(_) => {},
+                Err(reason) if failure.as_ref().map(|(created_at, _)| record.created_at() > *created_at).unwrap_or(true) => {
+                    failure = Some((record.created_at(), reason))
+                },
+                Err(_) => {},
+            }
+        }
+        reliance.ok_or_else(|| failure.map(|(_, reason)| reason).unwrap_or(RelianceFailure::NoAccreditation))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+    use time::macros::datetime;
+
+    // 2023-01-10 12:00 UTC
+    const JAN_10: i64 = 1673352000;
+    const DAY: i64 = 86400;
+
+    fn accreditation(status: &str, certified: bool, expires_at: i64) -> IndividualAccreditation {
+        let value = individual_accreditation("accreditation", status, JAN_10, Some(expires_at));
+        let certified_at = if certified { json!(JAN_10) } else { json!(null) };
+        serde_json::from_value(with(value, json!({ "certified_at": certified_at }))).unwrap()
+    }
+
+    fn check(accreditation: &IndividualAccreditation) -> Result<Reliance, RelianceFailure> {
+        ReliancePolicy::default().check(accreditation, datetime!(2023-02-01 00:00 UTC))
+    }
+
+    fn check_response(accreditations: Vec<serde_json::Value>) -> Result<Reliance, RelianceFailure> {
+        let response: AccreditationsResponse = serde_json::from_value(accreditations_response("individual", accreditations, None)).unwrap();
+        ReliancePolicy::default().check_response(&response, datetime!(2023-02-01 00:00 UTC))
+    }
+
+    #[test]
+    fn reliance_ends_with_the_look_back_window() {
+        let reliance = check(&accreditation("current", true, JAN_10 + 365 * DAY)).unwrap();
+
+        assert_eq!(reliance.ends_at, datetime!(2023-04-10 12:00 UTC));
+    }
+
+    #[test]
+    fn reliance_ends_with_an_earlier_expiry() {
+        let reliance = check(&accreditation("current", true, JAN_10 + 30 * DAY)).unwrap();
+
+        assert_eq!(reliance.ends_at, datetime!(2023-02-09 12:00 UTC));
+    }
+
+    #[test]
+    fn certifications_older_than_the_look_back_window_are_rejected() {
+        assert_eq!(
+            ReliancePolicy::with_look_back_days(14).check(&accreditation("current", true, JAN_10 + 365 * DAY), datetime!(2023-02-01 00:00 UTC)),
+            Err(RelianceFailure::WindowEnded {
+                certified_at: datetime!(2023-01-10 12:00 UTC),
+                ended_at: datetime!(2023-01-24 12:00 UTC),
+            })
+        );
+    }
+
+    #[test]
+    fn accreditations_without_certification_date_are_rejected() {
+        assert_eq!(check(&accreditation("current", false, JAN_10 + 365 * DAY)), Err(RelianceFailure::NoCertificationDate));
+    }
+
+    #[test]
+    fn accreditations_expired_at_the_offering_are_rejected() {
+        assert_eq!(
+            check(&accreditation("current", true, JAN_10 + 10 * DAY)),
+            Err(RelianceFailure::Expired {
+                at: Some(datetime!(2023-01-20 12:00 UTC))
+            })
+        );
+    }
+
+    #[test]
+    fn pending_accreditations_are_rejected() {
+        assert_eq!(
+            check(&accreditation("third_party_pending", false, JAN_10 + 365 * DAY)),
+            Err(RelianceFailure::Pending(AccreditationStatus::ThirdPartyPending))
+        );
+    }
+
+    #[test]
+    fn certifications_after_the_offering_are_rejected() {
+        assert_eq!(
+            ReliancePolicy::default().check(&accreditation("current", true, JAN_10 + 365 * DAY), datetime!(2023-01-01 00:00 UTC)),
+            Err(RelianceFailure::CertifiedAfterOffering {
+                certified_at: datetime!(2023-01-10 12:00 UTC)
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_statuses_are_rejected() {
+        assert_eq!(check(&accreditation("under_audit", false, JAN_10)), Err(RelianceFailure::UnknownStatus("under_audit".to_string())));
+    }
+
+    #[test]
+    fn any_qualifying_accreditation_is_enough() {
+        // Certified after the offering date, but with the latest expiry
+        let later = individual_accreditation("later", "current", JAN_10 + 30 * DAY, Some(JAN_10 + 730 * DAY));
+        let earlier = individual_accreditation("earlier", "current", JAN_10, Some(JAN_10 + 365 * DAY));
+
+        assert_eq!(check_response(vec![later, earlier]).unwrap().accreditation_id, "earlier");
+    }
+
+    #[test]
+    fn failure_of_the_most_promising_accreditation_is_reported() {
+        let later = individual_accreditation("later", "current", JAN_10 + 30 * DAY, Some(JAN_10 + 730 * DAY));
+        let unknown = individual_accreditation("unknown", "under_audit", JAN_10 - DAY, None);
+
+        assert_eq!(
+            check_response(vec![later, unknown]),
+            Err(RelianceFailure::CertifiedAfterOffering {
+                certified_at: datetime!(2023-02-09 12:00 UTC)
+            })
+        );
+    }
+}
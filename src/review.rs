@@ -0,0 +1,340 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::clock::*;
+use crate::datetime::lenient;
+use crate::json_file::*;
+use crate::risk::*;
+use crate::types::*;
+use crate::Result;
+use json_api_client::types::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewState {
+    Open,
+    /// Reviewed as a false positive
+    Cleared,
+    /// Reviewed as a possible true positive, needing further action
+    Escalated,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReviewRecord {
+    pub fingerprint: String,
+    pub subject_id: EntityId,
+    pub matched_name: String,
+    pub state: ReviewState,
+    pub reviewer: Option<String>,
+    pub reason: Option<String>,
+    #[serde(with = "lenient")]
+    pub updated_at: DateTime,
+    /// Flags of the match when the state was set
+    pub flags: Vec<RiskFlag>,
+    /// Score of the match when the state was set
+    pub score: u8,
+    /// Earlier states of the record, oldest first
+    #[serde(default)]
+    pub history: Vec<ReviewDecision>,
+}
+
+/// A state a review record had before it was changed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReviewDecision {
+    pub state: ReviewState,
+    pub reviewer: Option<String>,
+    pub reason: Option<String>,
+    #[serde(with = "lenient")]
+    pub updated_at: DateTime,
+    pub flags: Vec<RiskFlag>,
+    pub score: u8,
+}
+
+impl ReviewRecord {
+    /// The record with `decision` as its current state, and its current state added to the history
+    fn superseded_by(self, decision: ReviewDecision) -> ReviewRecord {
+        let mut history = self.history;
+        history.push(ReviewDecision {
+            state: self.state,
+            reviewer: self.reviewer,
+            reason: self.reason,
+            updated_at: self.updated_at,
+            flags: self.flags,
+            score: self.score,
+        });
+        ReviewRecord {
+            fingerprint: self.fingerprint,
+            subject_id: self.subject_id,
+            matched_name: self.matched_name,
+            state: decision.state,
+            reviewer: decision.reviewer,
+            reason: decision.reason,
+            updated_at: decision.updated_at,
+            flags: decision.flags,
+            score: decision.score,
+            history,
+        }
+    }
+}
+
+/// Stable ID of a risk match for a subject, the hex encoded SHA-256 of its identifying fields.
+/// Flags and score are not part of it, so a match keeps its fingerprint when they change.
+pub fn risk_match_fingerprint(subject_id: &str, risk_match: &dyn RiskMonitorMatch) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(subject_id.as_bytes());
+    for field in risk_match.identifying_fields() {
+        hasher.update([0]);
+        hasher.update(field.trim().to_lowercase().as_bytes());
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Storage of review records, keyed by fingerprint
+pub trait ReviewStore {
+    fn get(&self, fingerprint: &str) -> Result<Option<ReviewRecord>>;
+    fn put(&mut self, record: ReviewRecord) -> Result<()>;
+}
+
+#[derive(Debug, Default)]
+pub struct InMemoryReviewStore {
+    records: HashMap<String, ReviewRecord>,
+}
+
+impl ReviewStore for InMemoryReviewStore {
+    fn get(&self, fingerprint: &str) -> Result<Option<ReviewRecord>> {
+        Ok(self.records.get(fingerprint).cloned())
+    }
+
+    fn put(&mut self, record: ReviewRecord) -> Result<()> {
+        self.records.insert(record.fingerprint.clone(), record);
+        Ok(())
+    }
+}
+
+/// Keeps all records in one JSON file, rewritten on every change
+#[derive(Debug)]
+pub struct JsonFileReviewStore {
+    path: PathBuf,
+    records: BTreeMap<String, ReviewRecord>,
+}
+
+impl JsonFileReviewStore {
+    /// Loads the records from `path`, starting empty if the file doesn't exist
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<JsonFileReviewStore> {
+        let path = path.into();
+        let records = read_json_file(&path)?.unwrap_or_default();
+        Ok(JsonFileReviewStore { path, records })
+    }
+}
+
+impl ReviewStore for JsonFileReviewStore {
+    fn get(&self, fingerprint: &str) -> Result<Option<ReviewRecord>> {
+        Ok(self.records.get(fingerprint).cloned())
+    }
+
+    fn put(&mut self, record: ReviewRecord) -> Result<()> {
+        self.records.insert(record.fingerprint.clone(), record);
+        write_json_file(&self.path, &self.records)
+    }
+}
+
+/// A risk match that needs review
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingReview {
+    /// Index in `risk_monitor_matches`
+    pub match_index: usize,
+    pub record: ReviewRecord,
+    /// True if the match was cleared before, but its flags or score changed since
+    pub reopened: bool,
+}
+
+/// Tracks the review of risk matches across webhooks, suppressing matches cleared as false positives
+pub struct RiskReview<S: ReviewStore> {
+    store: S,
+    clock: Arc<dyn Clock>,
+}
+
+impl<S: ReviewStore> RiskReview<S> {
+    pub fn new(store: S) -> RiskReview<S> {
+        RiskReview {
+            store,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> RiskReview<S> {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Records new matches as open and returns every match that is not cleared.
+    /// Cleared matches whose flags or score changed are reopened, keeping the clearance in the record's history.
+    pub fn pending<M: RiskMonitorMatch>(&mut self, subject_id: &str, matches: &[M]) -> Result<Vec<PendingReview>> {
+        let mut pending = Vec::new();
+        for (match_index, risk_match) in matches.iter().enumerate() {
+            let fingerprint = risk_match_fingerprint(subject_id, risk_match);
+            let stored = self.store.get(&fingerprint)?;
+            let is_new = stored.is_none();
+            let (record, reopened) = match stored {
+                Some(record) if record.state == ReviewState::Cleared && record.flags == risk_match.flags() && record.score == risk_match.score() => continue,
+                Some(record) if record.state == ReviewState::Cleared => {
+                    (self.record(subject_id, risk_match, ReviewState::Open, None, None, Some(record)), true)
+                },
+                Some(record) => (record, false),
+                None => (self.record(subject_id, risk_match, ReviewState::Open, None, None, None), false),
+            };
+            if is_new || reopened {
+                self.store.put(record.clone())?;
+            }
+            pending.push(PendingReview { match_index, record, reopened });
+        }
+        Ok(pending)
+    }
+
+    /// Sets the review state of a match, recording its current flags and score. The previous state is kept in the history.
+    pub fn set_state(
+        &mut self,
+        subject_id: &str,
+        risk_match: &dyn RiskMonitorMatch,
+        state: ReviewState,
+        reviewer: &str,
+        reason: Option<&str>,
+    ) -> Result<ReviewRecord> {
+        let previous = self.store.get(&risk_match_fingerprint(subject_id, risk_match))?;
+        let record = self.record(subject_id, risk_match, state, Some(reviewer.to_string()), reason.map(str::to_string), previous);
+        self.store.put(record.clone())?;
+        Ok(record)
+    }
+
+    fn record(
+        &self,
+        subject_id: &str,
+        risk_match: &dyn RiskMonitorMatch,
+        state: ReviewState,
+        reviewer: Option<String>,
+        reason: Option<String>,
+        previous: Option<ReviewRecord>,
+    ) -> ReviewRecord {
+        let decision = ReviewDecision {
+            state,
+            reviewer,
+            reason,
+            updated_at: self.clock.now(),
+            flags: risk_match.flags(),
+            score: risk_match.score(),
+        };
+        match previous {
+            Some(previous) => previous.superseded_by(decision),
+            None => ReviewRecord {
+                fingerprint: risk_match_fingerprint(subject_id, risk_match),
+                subject_id: subject_id.to_string(),
+                matched_name: risk_match.matched_name(),
+                state: decision.state,
+                reviewer: decision.reviewer,
+                reason: decision.reason,
+                updated_at: decision.updated_at,
+                flags: decision.flags,
+                score: decision.score,
+                history: Vec::new(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use time::macros::datetime;
+
+    fn matches(values: Vec<serde_json::Value>) -> Vec<IndividualRiskMonitorMatch> {
+        serde_json::from_value(serde_json::Value::Array(values)).unwrap()
+    }
+
+    fn review() -> RiskReview<InMemoryReviewStore> {
+        RiskReview::new(InMemoryReviewStore::default()).with_clock(FixedClock(datetime!(2023-01-10 12:00 UTC)))
+    }
+
+    fn alice(flags: &[&str]) -> Vec<IndividualRiskMonitorMatch> {
+        matches(vec![individual_risk_match("Alice", "Smith", 40, flags)])
+    }
+
+    #[test]
+    fn new_matches_are_pending_as_open() {
+        let mut review = review();
+        let pending = review.pending("subject", &alice(&["pep"])).unwrap();
+
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].record.state, ReviewState::Open);
+        assert!(!pending[0].reopened);
+    }
+
+    #[test]
+    fn cleared_matches_are_suppressed() {
+        let mut review = review();
+        let first = matches(vec![
+            individual_risk_match("Alice", "Smith", 40, &["pep"]),
+            individual_risk_match("Bob", "Jones", 30, &[]),
+        ]);
+        review.set_state("subject", &first[0], ReviewState::Cleared, "analyst", None).unwrap();
+        review.set_state("subject", &first[1], ReviewState::Escalated, "analyst", None).unwrap();
+
+        let pending = review.pending("subject", &first).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!((pending[0].match_index, pending[0].record.state), (1, ReviewState::Escalated));
+    }
+
+    #[test]
+    fn cleared_matches_are_reopened_when_their_flags_change() {
+        let mut review = review();
+        review.set_state("subject", &alice(&["pep"])[0], ReviewState::Cleared, "analyst", None).unwrap();
+
+        let pending = review.pending("subject", &alice(&["pep", "adverse_media"])).unwrap();
+        assert!(pending[0].reopened);
+        assert_eq!(pending[0].record.state, ReviewState::Open);
+    }
+
+    #[test]
+    fn reopening_keeps_the_earlier_decisions() {
+        let mut review = review();
+        review.pending("subject", &alice(&["pep"])).unwrap();
+        review
+            .set_state("subject", &alice(&["pep"])[0], ReviewState::Cleared, "analyst", Some("different birth place"))
+            .unwrap();
+
+        let record = review.pending("subject", &alice(&["pep", "adverse_media"])).unwrap().remove(0).record;
+
+        let states: Vec<ReviewState> = record.history.iter().map(|decision| decision.state).collect();
+        assert_eq!(states, vec![ReviewState::Open, ReviewState::Cleared]);
+        assert_eq!(record.history[1].reviewer.as_deref(), Some("analyst"));
+        assert_eq!(record.history[1].reason.as_deref(), Some("different birth place"));
+        assert_eq!(record.state, ReviewState::Open);
+    }
+
+    #[test]
+    fn fingerprints_differ_per_subject() {
+        let alice = &alice(&["pep"])[0];
+
+        assert_ne!(risk_match_fingerprint("subject", alice), risk_match_fingerprint("other", alice));
+    }
+
+    #[test]
+    fn json_file_store_persists_records() {
+        let path = std::env::temp_dir().join(format!("risk-review-{}.json", std::process::id()));
+        let risk_match = &matches(vec![individual_risk_match("Alice", "Smith", 40, &["pep"])])[0];
+
+        let mut review = RiskReview::new(JsonFileReviewStore::open(&path).unwrap());
+        let record = review.set_state("subject", risk_match, ReviewState::Cleared, "analyst", None).unwrap();
+
+        let reopened = JsonFileReviewStore::open(&path).unwrap();
+        assert_eq!(reopened.get(&record.fingerprint).unwrap(), Some(record));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
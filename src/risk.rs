@@ -0,0 +1,119 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::types::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskFlag {
+    AdverseMedia,
+    CurrentlySanctioned,
+    PreviouslySanctioned,
+    DisqualifiedDirector,
+    FinancialRegulator,
+    Insolvent,
+    LawEnforcement,
+    Pep,
+    /// Only reported for individuals
+    Deceased,
+}
+
+impl fmt::Display for RiskFlag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            RiskFlag::AdverseMedia => "adverse_media",
+            RiskFlag::CurrentlySanctioned => "currently_sanctioned",
+            RiskFlag::PreviouslySanctioned => "previously_sanctioned",
+            RiskFlag::DisqualifiedDirector => "disqualified_director",
+            RiskFlag::FinancialRegulator => "financial_regulator",
+            RiskFlag::Insolvent => "insolvent",
+            RiskFlag::LawEnforcement => "law_enforcement",
+            RiskFlag::Pep => "pep",
+            RiskFlag::Deceased => "deceased",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Common view of `BusinessRiskMonitorMatch` and `IndividualRiskMonitorMatch`
+pub trait RiskMonitorMatch {
+    /// Flags set on the match, in `RiskFlag` order
+    fn flags(&self) -> Vec<RiskFlag>;
+    /// Entity match score 1-100
+    fn score(&self) -> u8;
+    /// Name of the matched entity
+    fn matched_name(&self) -> String;
+    /// Fields that identify the matched entity, excluding flags and score
+    fn identifying_fields(&self) -> Vec<String>;
+}
+
+impl RiskMonitorMatch for BusinessRiskMonitorMatch {
+    fn flags(&self) -> Vec<RiskFlag> {
+        [
+            (self.adverse_media, RiskFlag::AdverseMedia),
+            (self.currently_sanctioned, RiskFlag::CurrentlySanctioned),
+            (self.previously_sanctioned, RiskFlag::PreviouslySanctioned),
+            (self.disqualified_director, RiskFlag::DisqualifiedDirector),
+            (self.financial_regulator, RiskFlag::FinancialRegulator),
+            (self.insolvent, RiskFlag::Insolvent),
+            (self.law_enforcement, RiskFlag::LawEnforcement),
+            (self.pep, RiskFlag::Pep),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    fn score(&self) -> u8 {
+        self.score
+    }
+
+    fn matched_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn identifying_fields(&self) -> Vec<String> {
+        vec![self.name.clone(), self.website.clone()]
+    }
+}
+
+impl RiskMonitorMatch for IndividualRiskMonitorMatch {
+    fn flags(&self) -> Vec<RiskFlag> {
+        [
+            (self.adverse_media, RiskFlag::AdverseMedia),
+            (self.currently_sanctioned, RiskFlag::CurrentlySanctioned),
+            (self.previously_sanctioned, RiskFlag::PreviouslySanctioned),
+            (self.disqualified_director, RiskFlag::DisqualifiedDirector),
+            (self.financial_regulator, RiskFlag::FinancialRegulator),
+            (self.insolvent, RiskFlag::Insolvent),
+            (self.law_enforcement, RiskFlag::LawEnforcement),
+            (self.pep, RiskFlag::Pep),
+            (self.deceased, RiskFlag::Deceased),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .collect()
+    }
+
+    fn score(&self) -> u8 {
+        self.score
+    }
+
+    fn matched_name(&self) -> String {
+        [self.first_name.as_str(), self.middle_name.as_str(), self.last_name.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn identifying_fields(&self) -> Vec<String> {
+        vec![
+            self.first_name.clone(),
+            self.middle_name.clone(),
+            self.last_name.clone(),
+            self.birth_date.to_string(),
+            self.nationality.clone(),
+        ]
+    }
+}
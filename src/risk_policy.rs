@@ -0,0 +1,236 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::config;
+use crate::risk::*;
+use crate::types::*;
+use crate::{Error, Result};
+
+/// Outcome of a risk policy, ordered from least to most severe
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskOutcome {
+    AutoApprove,
+    ManualReview,
+    Reject,
+}
+
+impl fmt::Display for RiskOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            RiskOutcome::AutoApprove => "auto_approve",
+            RiskOutcome::ManualReview => "manual_review",
+            RiskOutcome::Reject => "reject",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Matches a risk monitor match if all of its conditions hold. A rule without conditions matches every match.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RiskRule {
+    pub name: String,
+    /// Every one of these flags must be set
+    #[serde(default)]
+    pub all_flags: Vec<RiskFlag>,
+    /// At least one of these flags must be set, ignored if empty
+    #[serde(default)]
+    pub any_flags: Vec<RiskFlag>,
+    #[serde(default)]
+    pub min_score: Option<u8>,
+    #[serde(default)]
+    pub max_score: Option<u8>,
+    pub outcome: RiskOutcome,
+}
+
+impl RiskRule {
+    pub fn matches(&self, risk_match: &dyn RiskMonitorMatch) -> bool {
+        let flags = risk_match.flags();
+        let score = risk_match.score();
+        self.all_flags.iter().all(|flag| flags.contains(flag))
+            && (self.any_flags.is_empty() || self.any_flags.iter().any(|flag| flags.contains(flag)))
+            && self.min_score.map(|min| score >= min).unwrap_or(true)
+            && self.max_score.map(|max| score <= max).unwrap_or(true)
+    }
+}
+
+/// Declarative mapping from risk monitor matches to an outcome, e.g. in TOML:
+///
+/// ```toml
+/// default_outcome = "auto_approve"
+///
+/// [[rules]]
+/// name = "sanctioned"
+/// any_flags = ["currently_sanctioned"]
+/// outcome = "reject"
+///
+/// [[rules]]
+/// name = "strong pep match"
+/// all_flags = ["pep"]
+/// min_score = 80
+/// outcome = "manual_review"
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RiskPolicy {
+    /// Outcome of a risk monitor match no rule matches, and of an identity without risk monitor matches
+    #[serde(default = "default_outcome")]
+    pub default_outcome: RiskOutcome,
+    #[serde(default)]
+    pub rules: Vec<RiskRule>,
+}
+
+fn default_outcome() -> RiskOutcome {
+    RiskOutcome::AutoApprove
+}
+
+/// A rule that matched one of the risk monitor matches
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct MatchedRule {
+    pub rule: String,
+    pub outcome: RiskOutcome,
+    /// Index in `risk_monitor_matches`
+    pub match_index: usize,
+    pub matched_name: String,
+    pub score: u8,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RiskDecision {
+    /// The most severe outcome across the risk monitor matches. Each match's outcome is the most severe of its rules,
+    /// or the policy's default outcome if no rule matched it.
+    pub outcome: RiskOutcome,
+    pub matched_rules: Vec<MatchedRule>,
+}
+
+impl RiskPolicy {
+    pub fn from_toml(policy: &str) -> Result<RiskPolicy> {
+        let policy: RiskPolicy = config::from_toml("risk policy", policy)?;
+        policy.validate()
+    }
+
+    pub fn from_json(policy: &str) -> Result<RiskPolicy> {
+        let policy: RiskPolicy = config::from_json("risk policy", policy)?;
+        policy.validate()
+    }
+
+    fn validate(self) -> Result<RiskPolicy> {
+        for rule in &self.rules {
+            if let (Some(min), Some(max)) = (rule.min_score, rule.max_score) {
+                if min > max {
+                    return Err(Error::ConfigError(format!("risk policy: rule '{}' has min_score {} above max_score {}", rule.name, min, max)));
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn evaluate<M: RiskMonitorMatch>(&self, matches: &[M]) -> RiskDecision {
+        let mut matched_rules = Vec::new();
+        let mut outcome = None;
+        for (match_index, risk_match) in matches.iter().enumerate() {
+            let mut match_outcome = None;
+            for rule in self.rules.iter().filter(|rule| rule.matches(risk_match)) {
+                match_outcome = match_outcome.max(Some(rule.outcome));
+                matched_rules.push(MatchedRule {
+                    rule: rule.name.clone(),
+                    outcome: rule.outcome,
+                    match_index,
+                    matched_name: risk_match.matched_name(),
+                    score: risk_match.score(),
+                });
+            }
+            outcome = outcome.max(Some(match_outcome.unwrap_or(self.default_outcome)));
+        }
+
+        RiskDecision {
+            outcome: outcome.unwrap_or(self.default_outcome),
+            matched_rules,
+        }
+    }
+
+    /// Evaluates the `risk_monitor_matches` of an individual or business identity
+    pub fn evaluate_identity(&self, details: &IdentityDetails) -> RiskDecision {
+        match details {
+            IdentityDetails::Individual(individual) => self.evaluate(&individual.risk_monitor_matches),
+            IdentityDetails::Business(business) => self.evaluate(&business.risk_monitor_matches),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    const POLICY: &str = r#"
+        [[rules]]
+        name = "sanctioned"
+        any_flags = ["currently_sanctioned", "previously_sanctioned"]
+        outcome = "reject"
+
+        [[rules]]
+        name = "strong pep match"
+        all_flags = ["pep"]
+        min_score = 80
+        outcome = "manual_review"
+
+        [[rules]]
+        name = "weak match"
+        max_score = 30
+        outcome = "auto_approve"
+    "#;
+
+    fn matches(values: Vec<serde_json::Value>) -> Vec<IndividualRiskMonitorMatch> {
+        serde_json::from_value(serde_json::Value::Array(values)).unwrap()
+    }
+
+    #[test]
+    fn most_severe_matched_rule_wins() {
+        let policy = RiskPolicy::from_toml(POLICY).unwrap();
+
+        let decision = policy.evaluate(&matches(vec![
+            individual_risk_match("Alice", "Smith", 20, &["pep"]),
+            individual_risk_match("Alice", "Smyth", 85, &["pep", "adverse_media"]),
+        ]));
+        assert_eq!(decision.outcome, RiskOutcome::ManualReview);
+        let rules: Vec<(&str, usize)> = decision.matched_rules.iter().map(|r| (r.rule.as_str(), r.match_index)).collect();
+        assert_eq!(rules, vec![("weak match", 0), ("strong pep match", 1)]);
+
+        let decision = policy.evaluate(&matches(vec![individual_risk_match("Alice", "Smith", 10, &["previously_sanctioned"])]));
+        assert_eq!(decision.outcome, RiskOutcome::Reject);
+        assert_eq!(policy.evaluate::<IndividualRiskMonitorMatch>(&[]).outcome, RiskOutcome::AutoApprove);
+    }
+
+    #[test]
+    fn unruled_matches_get_the_default_outcome() {
+        let policy = RiskPolicy::from_json(r#"{ "default_outcome": "manual_review", "rules": [] }"#).unwrap();
+
+        assert_eq!(policy.evaluate(&matches(vec![individual_risk_match("Alice", "Smith", 50, &[])])).outcome, RiskOutcome::ManualReview);
+    }
+
+    #[test]
+    fn default_outcome_wins_when_more_severe_than_the_matched_rules() {
+        let policy =
+            RiskPolicy::from_json(r#"{ "default_outcome": "manual_review", "rules": [{ "name": "weak match", "max_score": 30, "outcome": "auto_approve" }] }"#)
+                .unwrap();
+
+        let decision = policy.evaluate(&matches(vec![
+            individual_risk_match("Alice", "Smith", 20, &[]),
+            individual_risk_match("Alice", "Smyth", 70, &[]),
+        ]));
+        assert_eq!(decision.outcome, RiskOutcome::ManualReview);
+        assert_eq!(decision.matched_rules.len(), 1);
+    }
+
+    #[test]
+    fn unknown_rule_fields_are_rejected() {
+        assert!(RiskPolicy::from_toml("[[rules]]\nname = \"typo\"\nany_flag = [\"pep\"]\noutcome = \"reject\"").is_err());
+    }
+
+    #[test]
+    fn empty_score_ranges_are_rejected() {
+        assert!(RiskPolicy::from_toml("[[rules]]\nname = \"range\"\nmin_score = 90\nmax_score = 10\noutcome = \"reject\"").is_err());
+    }
+}
@@ -0,0 +1,184 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{compiler_fence, Ordering};
+use zeroize::Zeroize;
+
+use json_api_client::types::Date;
+use json_api_client::StandardToken;
+use serde_json::Value;
+
+/// Sensitive value, redacted in `Debug` and `Display` and overwritten when dropped.
+/// Serialization is not redacted, so responses can still be stored or forwarded as JSON.
+///
+/// Names and addresses are left as plain strings: ownership checks, triage and address normalization match on them,
+/// so `Debug` output of identities and profiles still must not be logged.
+pub struct Secret<T: Erase>(T);
+
+impl<T: Erase> Secret<T> {
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Erase> From<T> for Secret<T> {
+    fn from(value: T) -> Secret<T> {
+        Secret(value)
+    }
+}
+
+impl<T: Erase> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.erase();
+    }
+}
+
+impl<T: Erase + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Secret<T> {
+        Secret(self.0.clone())
+    }
+}
+
+impl<T: Erase + PartialEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Secret<T>) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Erase> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+impl<T: Erase> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+impl<T: Erase + FromStr> FromStr for Secret<T> {
+    type Err = T::Err;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        value.parse().map(Secret)
+    }
+}
+
+impl<T: Erase + Serialize> Serialize for Secret<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl<'de, T: Erase + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+/// Overwrites the memory of a value, called when a `Secret` is dropped. Strings are zeroized with volatile writes,
+/// other implementations may only be best effort.
+pub trait Erase {
+    fn erase(&mut self);
+}
+
+impl Erase for String {
+    fn erase(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// Best effort: the date is replaced with `Date::MIN`, but this is a plain store, which the compiler may still drop
+/// as dead, and `Date` is `Copy`, so copies made while the secret was alive aren't overwritten
+impl Erase for Date {
+    fn erase(&mut self) {
+        *self = Date::MIN;
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Overwrites every string in the value, object keys are left as they are field names
+impl Erase for Value {
+    fn erase(&mut self) {
+        match self {
+            Value::String(value) => value.zeroize(),
+            Value::Array(values) => values.iter_mut().for_each(Erase::erase),
+            Value::Object(fields) => fields.values_mut().for_each(Erase::erase),
+            Value::Null | Value::Bool(_) | Value::Number(_) => {},
+        }
+    }
+}
+
+/// Token returned by `Client::exchange_code` and `Client::refresh_token`, redacted in `Debug`.
+/// Unlike `Secret` it isn't overwritten when dropped, as the token fields are private to `json_api_client`.
+pub struct RedactedToken(StandardToken);
+
+impl RedactedToken {
+    pub fn expose(&self) -> &StandardToken {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> StandardToken {
+        self.0
+    }
+}
+
+impl From<StandardToken> for RedactedToken {
+    fn from(token: StandardToken) -> RedactedToken {
+        RedactedToken(token)
+    }
+}
+
+impl fmt::Debug for RedactedToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[REDACTED]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use crate::types::*;
+
+    #[test]
+    fn debug_output_is_redacted() {
+        let identity = with(
+            individual_identity("Alice", "Smith"),
+            serde_json::json!({ "identity_files": [{
+                "download_url": "https://example.com/passport.jpg?signature=abc",
+                "download_url_expires": 300,
+                "type": "passport",
+            }] }),
+        );
+        let details: IndividualIdentityDetails = serde_json::from_value(identity.clone()).unwrap();
+
+        let debug = format!("{:#?}", details);
+        for sensitive in ["123-45-6789", "6789", "1980", "0123", "signature"] {
+            assert!(!debug.contains(sensitive), "{} in {}", sensitive, debug);
+        }
+        assert_eq!(details.us_tax_id.as_ref().unwrap().expose().to_string(), "123-45-6789");
+        assert_eq!(serde_json::to_value(&details).unwrap()["phone"], identity["phone"]);
+    }
+
+    #[test]
+    fn erase_overwrites_the_value() {
+        let mut value = "123-45-6789".to_string();
+        value.erase();
+        assert!(value.is_empty());
+
+        let mut date = Date::MAX;
+        date.erase();
+        assert_eq!(date, Date::MIN);
+
+        let mut body = serde_json::json!({ "us_tax_id": "123-45-6789", "owners": [{ "name": "Alice" }] });
+        body.erase();
+        assert_eq!(body, serde_json::json!({ "us_tax_id": "", "owners": [{ "name": "" }] }));
+    }
+}
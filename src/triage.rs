@@ -0,0 +1,344 @@
+use serde::Serialize;
+use strsim::jaro_winkler;
+use url::Url;
+
+use crate::countries::*;
+use crate::types::*;
+
+/// Name similarity (Jaro-Winkler, 0-1) from which names are considered the same
+pub const SAME_NAME_SIMILARITY: f64 = 0.92;
+/// Name similarity below which names are considered different
+pub const DIFFERENT_NAME_SIMILARITY: f64 = 0.80;
+
+/// Legal form suffixes ignored when comparing business names
+const BUSINESS_SUFFIXES: [&str; 11] = [
+    "inc",
+    "incorporated",
+    "llc",
+    "ltd",
+    "limited",
+    "corp",
+    "corporation",
+    "co",
+    "company",
+    "lp",
+    "plc",
+];
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TriageVerdict {
+    LikelySame,
+    PossiblySame,
+    LikelyDifferent,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DateComparison {
+    Same,
+    /// Same year, different day
+    SameYear,
+    Different,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "evidence", rename_all = "snake_case")]
+pub enum TriageEvidence {
+    /// Similarity of the normalized names, 0-1
+    NameSimilarity {
+        similarity: f64,
+    },
+    BirthDate {
+        comparison: DateComparison,
+    },
+    /// `matches` is None if the reported nationality isn't a recognized country code, name or demonym
+    Nationality {
+        reported: String,
+        subject_country: String,
+        matches: Option<bool>,
+    },
+    /// Domain of the matched entity's website compared with the domains of the subject's contact emails
+    WebsiteDomain {
+        domain: String,
+        matches_contact_email: bool,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TriagedMatch {
+    /// Index in `risk_monitor_matches`
+    pub match_index: usize,
+    pub matched_name: String,
+    pub verdict: TriageVerdict,
+    pub evidence: Vec<TriageEvidence>,
+}
+
+/// Compares each risk monitor match with the subject. Triage only helps ordering the review, it doesn't clear matches.
+pub fn triage_risk_matches(details: &IdentityDetails) -> Vec<TriagedMatch> {
+    match details {
+        IdentityDetails::Individual(individual) => triage_individual_matches(individual),
+        IdentityDetails::Business(business) => triage_business_matches(business),
+    }
+}
+
+pub fn triage_individual_matches(details: &IndividualIdentityDetails) -> Vec<TriagedMatch> {
+    let subject_name = normalize_name(&format!("{} {}", details.first_name, details.last_name));
+    let subject_country = country_code(&details.citizenship_country);
+    let birth_date = *details.birth_date.expose();
+
+    details
+        .risk_monitor_matches
+        .iter()
+        .enumerate()
+        .map(|(match_index, risk_match)| {
+            // The subject has no middle name, so compare with and without the matched middle name
+            let without_middle = normalize_name(&format!("{} {}", risk_match.first_name, risk_match.last_name));
+            let with_middle = normalize_name(&format!("{} {} {}", risk_match.first_name, risk_match.middle_name, risk_match.last_name));
+            let similarity = jaro_winkler(&subject_name, &without_middle).max(jaro_winkler(&subject_name, &with_middle));
+
+            let birth_date_comparison = if risk_match.birth_date == birth_date {
+                DateComparison::Same
+            } else if risk_match.birth_date.year() == birth_date.year() {
+                DateComparison::SameYear
+            } else {
+                DateComparison::Different
+            };
+            let nationality_matches = compare_nationality(&risk_match.nationality, subject_country.as_deref());
+
+            let verdict = if birth_date_comparison == DateComparison::Different || similarity < DIFFERENT_NAME_SIMILARITY {
+                TriageVerdict::LikelyDifferent
+            } else if birth_date_comparison == DateComparison::Same && similarity >= SAME_NAME_SIMILARITY && nationality_matches != Some(false) {
+                TriageVerdict::LikelySame
+            } else {
+                TriageVerdict::PossiblySame
+            };
+
+            let mut evidence = vec![
+                TriageEvidence::NameSimilarity { similarity },
+                TriageEvidence::BirthDate {
+                    comparison: birth_date_comparison,
+                },
+            ];
+            if !risk_match.nationality.is_empty() {
+                evidence.push(TriageEvidence::Nationality {
+                    reported: risk_match.nationality.clone(),
+                    subject_country: subject_country.clone().unwrap_or_default(),
+                    matches: nationality_matches,
+                });
+            }
+
+            TriagedMatch {
+                match_index,
+                matched_name: full_name(&[&risk_match.first_name, &risk_match.middle_name, &risk_match.last_name]),
+                verdict,
+                evidence,
+            }
+        })
+        .collect()
+}
+
+pub fn triage_business_matches(details: &BusinessIdentityDetails) -> Vec<TriagedMatch> {
+    let subject_name = normalize_business_name(&details.name);
+    let contact_domains: Vec<String> = [&details.primary_contact, &details.created_by]
+        .into_iter()
+        .filter_map(|contact| contact.parse_email().ok().flatten())
+        .map(|email| normalize_domain(email.expose().domain()))
+        .collect();
+
+    details
+        .risk_monitor_matches
+        .iter()
+        .enumerate()
+        .map(|(match_index, risk_match)| {
+            let similarity = jaro_winkler(&subject_name, &normalize_business_name(&risk_match.name));
+            let domain = website_domain(&risk_match.website);
+            let domain_matches = domain.as_ref().map(|domain| contact_domains.contains(domain));
+
+            let verdict = match domain_matches {
+                Some(true) if similarity >= DIFFERENT_NAME_SIMILARITY => TriageVerdict::LikelySame,
+                _ if similarity < DIFFERENT_NAME_SIMILARITY => TriageVerdict::LikelyDifferent,
+                Some(false) if similarity < SAME_NAME_SIMILARITY => TriageVerdict::LikelyDifferent,
+                _ => TriageVerdict::PossiblySame,
+            };
+
+            let mut evidence = vec![TriageEvidence::NameSimilarity { similarity }];
+            if let (Some(domain), Some(matches_contact_email)) = (domain, domain_matches) {
+                evidence.push(TriageEvidence::WebsiteDomain { domain, matches_contact_email });
+            }
+
+            TriagedMatch {
+                match_index,
+                matched_name: risk_match.name.clone(),
+                verdict,
+                evidence,
+            }
+        })
+        .collect()
+}
+
+/// Non-empty name parts separated by single spaces
+fn full_name(parts: &[&str]) -> String {
+    parts.iter().map(|part| part.trim()).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercase alphanumeric words, separated by single spaces
+fn normalize_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn normalize_business_name(name: &str) -> String {
+    let name = normalize_name(name);
+    let words: Vec<&str> = name.split(' ').collect();
+    let end = words
+        .iter()
+        .rposition(|word| !BUSINESS_SUFFIXES.contains(word))
+        .map(|i| i + 1)
+        .unwrap_or(words.len());
+    words[..end].join(" ")
+}
+
+/// Host of a website without `www.`, None if the website is empty or invalid
+fn website_domain(website: &str) -> Option<String> {
+    let website = website.trim();
+    if website.is_empty() {
+        return None;
+    }
+    let url = Url::parse(website).or_else(|_| Url::parse(&format!("http://{}", website))).ok()?;
+    url.host_str().map(normalize_domain)
+}
+
+fn normalize_domain(host: &str) -> String {
+    let host = host.trim().to_lowercase();
+    host.strip_prefix("www.").map(str::to_string).unwrap_or(host)
+}
+
+/// Nationalities can be reported as a country code, a country name or a demonym, e.g. `US`, `United States` or `American`.
+/// None if the nationality isn't recognized.
+fn compare_nationality(reported: &str, subject_country: Option<&str>) -> Option<bool> {
+    let subject_country = subject_country?;
+    Some(country_code_by_name(reported)?.eq_ignore_ascii_case(subject_country))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+
+    /// Triages a single match against Alice Smith, born 1980-05-17 with US citizenship
+    fn triage_individual(risk_match: serde_json::Value, overrides: serde_json::Value) -> TriagedMatch {
+        let identity = with(individual_identity("Alice", "Smith"), json!({ "risk_monitor_matches": [risk_match] }));
+        let details: IndividualIdentityDetails = serde_json::from_value(with(identity, overrides)).unwrap();
+        triage_individual_matches(&details).remove(0)
+    }
+
+    /// Triages a single match against Acme Holdings, Inc. whose contact has an `acme-holdings.com` email
+    fn triage_business(name: &str, website: &str) -> TriagedMatch {
+        let risk_match = json!({
+            "adverse_media": true, "currently_sanctioned": false, "disqualified_director": false, "financial_regulator": false,
+            "insolvent": false, "law_enforcement": false, "name": name, "pep": false, "previously_sanctioned": false,
+            "score": 70, "website": website,
+        });
+        let identity = with(
+            business_identity("Acme Holdings, Inc.", vec![], vec![]),
+            json!({ "primary_contact": { "email": "jane@acme-holdings.com" }, "risk_monitor_matches": [risk_match] }),
+        );
+        let details: BusinessIdentityDetails = serde_json::from_value(identity).unwrap();
+        triage_business_matches(&details).remove(0)
+    }
+
+    #[test]
+    fn same_name_and_birth_date_are_likely_same() {
+        let triaged = triage_individual(individual_risk_match("Alice", "Smith", 90, &["pep"]), json!({}));
+
+        assert_eq!(triaged.verdict, TriageVerdict::LikelySame);
+        assert_eq!(triaged.evidence[0], TriageEvidence::NameSimilarity { similarity: 1.0 });
+    }
+
+    #[test]
+    fn different_birth_dates_are_likely_different() {
+        let born_earlier = with(individual_risk_match("Alice", "Smith", 90, &["pep"]), json!({ "birth_date": "1955-02-01" }));
+
+        assert_eq!(triage_individual(born_earlier, json!({})).verdict, TriageVerdict::LikelyDifferent);
+    }
+
+    #[test]
+    fn similar_names_born_the_same_year_are_possibly_same() {
+        let same_year = with(individual_risk_match("Alicia", "Smith", 60, &[]), json!({ "birth_date": "1980-11-30" }));
+        let triaged = triage_individual(same_year, json!({}));
+
+        assert_eq!(triaged.verdict, TriageVerdict::PossiblySame);
+        assert_eq!(
+            triaged.evidence[1],
+            TriageEvidence::BirthDate {
+                comparison: DateComparison::SameYear
+            }
+        );
+    }
+
+    #[test]
+    fn different_names_are_likely_different() {
+        let triaged = triage_individual(individual_risk_match("Bob", "Jones", 40, &[]), json!({}));
+
+        assert_eq!(triaged.verdict, TriageVerdict::LikelyDifferent);
+    }
+
+    #[test]
+    fn matched_names_include_the_middle_name() {
+        let with_middle_name = with(individual_risk_match("Alice", "Smith", 90, &[]), json!({ "middle_name": "Mary" }));
+
+        assert_eq!(triage_individual(with_middle_name, json!({})).matched_name, "Alice Mary Smith");
+        assert_eq!(triage_individual(individual_risk_match("Alice", "Smith", 90, &[]), json!({})).matched_name, "Alice Smith");
+    }
+
+    #[test]
+    fn nationality_names_are_compared_by_country_code() {
+        let triaged = triage_individual(individual_risk_match("Alice", "Smith", 90, &[]), json!({}));
+
+        assert_eq!(
+            triaged.evidence[2],
+            TriageEvidence::Nationality {
+                reported: "United States".to_string(),
+                subject_country: "US".to_string(),
+                matches: Some(true),
+            }
+        );
+    }
+
+    #[test]
+    fn different_nationalities_make_matches_possibly_same() {
+        let triaged = triage_individual(individual_risk_match("Alice", "Smith", 90, &[]), json!({ "citizenship_country": "GB" }));
+
+        assert_eq!(triaged.verdict, TriageVerdict::PossiblySame);
+    }
+
+    #[test]
+    fn businesses_with_the_contact_email_domain_as_website_are_likely_same() {
+        let triaged = triage_business("ACME Holdings LLC", "https://www.acme-holdings.com/about");
+
+        assert_eq!(triaged.verdict, TriageVerdict::LikelySame);
+        assert_eq!(
+            triaged.evidence[1],
+            TriageEvidence::WebsiteDomain {
+                domain: "acme-holdings.com".to_string(),
+                matches_contact_email: true,
+            }
+        );
+    }
+
+    #[test]
+    fn businesses_with_the_same_name_but_no_matching_website_are_possibly_same() {
+        assert_eq!(triage_business("Acme Holdings Ltd", "").verdict, TriageVerdict::PossiblySame);
+        assert_eq!(triage_business("Acme Holdings Ltd", "acme.example.org").verdict, TriageVerdict::PossiblySame);
+    }
+
+    #[test]
+    fn differently_named_businesses_are_likely_different() {
+        assert_eq!(triage_business("Zenith Trading", "").verdict, TriageVerdict::LikelyDifferent);
+    }
+}
@@ -1,7 +1,11 @@
-use either::Either;
+use serde::de::{self, DeserializeOwned, Deserializer};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
 
+use crate::datetime::lenient;
+use crate::secret::{Erase, Secret};
+use crate::values::*;
 use json_api_client::types::*;
 
 pub type EntityId = String;
@@ -12,6 +16,9 @@ pub enum Scope {
     Profile,
     AccreditationStatus,
     Identity,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 impl fmt::Display for Scope {
@@ -20,6 +27,7 @@ impl fmt::Display for Scope {
             Scope::Profile => "profile",
             Scope::AccreditationStatus => "accreditation_status",
             Scope::Identity => "identity",
+            Scope::Unknown(scope) => scope,
         };
         write!(f, "{}", str)
     }
@@ -42,7 +50,7 @@ pub struct UserSession {
     pub maybe_anonymizing_proxy: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BusinessType {
     /// A public charity as defined in Section 501(c)(3) of the Internal Revenue Code
     #[serde(rename = "Public Charity")]
@@ -71,6 +79,27 @@ pub enum BusinessType {
     /// A "family office" as defined in Rule 202(a)(11)(G)–1 under the Investment Advisers Act of 1940
     #[serde(rename = "Family Office")]
     FamilyOffice,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl fmt::Display for BusinessType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            BusinessType::PublicCharity => "Public Charity",
+            BusinessType::PrivateFoundation => "Private Foundation",
+            BusinessType::SCorporation => "S Corporation",
+            BusinessType::CCorporation => "C Corporation",
+            BusinessType::IrrevocableTrust => "Irrevocable Trust",
+            BusinessType::RevocableTrust => "Revocable Trust",
+            BusinessType::PartnershipLLC => "Partnership LLC",
+            BusinessType::PartnershipLP => "Partnership LP",
+            BusinessType::FamilyOffice => "Family Office",
+            BusinessType::Unknown(business_type) => business_type,
+        };
+        write!(f, "{}", str)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -84,25 +113,38 @@ pub struct BusinessProfile {
 pub struct IndividualProfile {
     pub first_name: String,
     pub last_name: String,
-    pub email: Option<String>,
+    /// As received, see `parse_email()`
+    pub email: Option<Secret<String>>,
+}
+
+impl IndividualProfile {
+    pub fn parse_email(&self) -> Result<Option<Secret<EmailAddress>>, InvalidValue> {
+        parse_optional(&self.email)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum DocumentType {
     CertificationLetter,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AccreditationDocument {
-    pub download_url: String,
+    pub download_url: Secret<String>,
     /// Number of seconds until expiration
     pub download_url_expires: u64,
     #[serde(rename = "type")]
     pub document_type: DocumentType,
+    /// When the document was fetched from the API, set by `Client`. `download_url_expires` counts from this moment.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum AccreditationStatus {
     /// Accreditation is currently valid
@@ -115,9 +157,12 @@ pub enum AccreditationStatus {
     Expired,
     /// Accreditation attempt was unsuccessful due to a failure to meet requirements
     Rejected,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum BusinessAssertionType {
     /// Accreditation is based on worth (only used for businesses)
@@ -126,18 +171,33 @@ pub enum BusinessAssertionType {
     EvaluatorAssertion,
     /// Accreditation is based on all owners being accredited
     AccreditedOwners,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl fmt::Display for BusinessAssertionType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            BusinessAssertionType::Worth => "worth",
+            BusinessAssertionType::EvaluatorAssertion => "evaluator-assertion",
+            BusinessAssertionType::AccreditedOwners => "accredited-owners",
+            BusinessAssertionType::Unknown(assertion_type) => assertion_type,
+        };
+        write!(f, "{}", str)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BusinessAccreditation {
     pub id: String,
     pub status: AccreditationStatus,
-    #[serde(with = "time::serde::timestamp::option")]
+    #[serde(with = "lenient::option")]
     pub expires_at: Option<DateTime>,
     pub assertion_type: BusinessAssertionType,
-    #[serde(with = "time::serde::timestamp")]
+    #[serde(with = "lenient")]
     pub created_at: DateTime,
-    #[serde(with = "time::serde::timestamp::option")]
+    #[serde(with = "lenient::option")]
     pub certified_at: Option<DateTime>,
     pub name: String,
     pub documents: Vec<AccreditationDocument>,
@@ -154,39 +214,198 @@ pub enum IndividualAssertionType {
     EvaluatorAssertion,
     /// The person holds a professional license that qualifies them for accreditation
     ProfessionalLicense,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IndividualAccreditation {
     pub id: String,
     pub status: AccreditationStatus,
-    #[serde(with = "time::serde::timestamp::option")]
+    #[serde(with = "lenient::option")]
     pub expires_at: Option<DateTime>,
     pub assertion_type: IndividualAssertionType,
-    #[serde(with = "time::serde::timestamp")]
+    #[serde(with = "lenient")]
     pub created_at: DateTime,
-    #[serde(with = "time::serde::timestamp::option")]
+    #[serde(with = "lenient::option")]
     pub certified_at: Option<DateTime>,
     pub first_name: String,
     pub last_name: String,
     pub documents: Vec<AccreditationDocument>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum EntityType {
     Individual,
     Business,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
+}
+
+impl fmt::Display for EntityType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            EntityType::Individual => "individual",
+            EntityType::Business => "business",
+            EntityType::Unknown(entity_type) => entity_type,
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Payload whose shape is selected by a sibling `type` field. Structs carrying one are generic over it, so their
+/// derived impl reads the payload as a `Secret<serde_json::Value>`, which is erased once `from_tagged` parsed it.
+trait EntityTagged: Sized {
+    type Individual: DeserializeOwned;
+    type Business: DeserializeOwned;
+
+    fn individual(value: Self::Individual) -> Self;
+    fn business(value: Self::Business) -> Self;
+
+    fn from_tagged<E: de::Error>(entity_type: &EntityType, field: &str, value: &Secret<serde_json::Value>) -> Result<Self, E> {
+        let result = match entity_type {
+            EntityType::Individual => serde_path_to_error::deserialize(value.expose()).map(Self::individual),
+            EntityType::Business => serde_path_to_error::deserialize(value.expose()).map(Self::business),
+            EntityType::Unknown(_) => {
+                return Err(E::custom(PayloadError {
+                    entity_type: entity_type.clone(),
+                    path: field.to_string(),
+                    cause: "unsupported type".to_string(),
+                }))
+            },
+        };
+        result.map_err(|e| {
+            let path = join_path(field, &e.path().to_string());
+            let cause = e.into_inner().to_string();
+            // The failure may be in a payload nested in this one, e.g. an owner's profile, whose error has the rest of the path
+            let error = match PayloadError::parse(&cause) {
+                Some((nested_path, nested_cause)) => PayloadError {
+                    entity_type: entity_type.clone(),
+                    path: join_path(&path, nested_path),
+                    cause: nested_cause.to_string(),
+                },
+                None => PayloadError {
+                    entity_type: entity_type.clone(),
+                    path,
+                    cause,
+                },
+            };
+            E::custom(error)
+        })
+    }
+}
+
+/// Failure of a tagged payload. Its path relative to the struct holding the payload is part of the message, the only
+/// thing a deserializer error keeps, so that `parse_response` and enclosing payloads can recover it.
+struct PayloadError {
+    entity_type: EntityType,
+    path: String,
+    cause: String,
+}
+
+impl PayloadError {
+    /// Path and cause of an error printed by `PayloadError`
+    fn parse(message: &str) -> Option<(&str, &str)> {
+        let (_, rest) = message.strip_prefix("invalid ")?.split_once('`')?;
+        rest.split_once("`: ")
+    }
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid {} `{}`: {}", self.entity_type, self.path, self.cause)
+    }
+}
+
+/// Path of the failure inside a tagged payload, if the error `message` comes from one
+pub(crate) fn payload_error_path(message: &str) -> Option<&str> {
+    PayloadError::parse(message).map(|(path, _)| path)
+}
+
+/// Joins paths as printed by `serde_path_to_error`, where `.` is the root
+pub(crate) fn join_path(prefix: &str, suffix: &str) -> String {
+    match (prefix, suffix) {
+        (".", _) => suffix.to_string(),
+        (_, ".") => prefix.to_string(),
+        (_, _) if suffix.starts_with('[') => format!("{}{}", prefix, suffix),
+        (_, _) => format!("{}.{}", prefix, suffix),
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Profile {
+    Individual(IndividualProfile),
+    Business(BusinessProfile),
+}
+
+impl Profile {
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            Profile::Individual(_) => EntityType::Individual,
+            Profile::Business(_) => EntityType::Business,
+        }
+    }
+
+    pub fn as_individual(&self) -> Option<&IndividualProfile> {
+        match self {
+            Profile::Individual(profile) => Some(profile),
+            Profile::Business(_) => None,
+        }
+    }
+
+    pub fn as_business(&self) -> Option<&BusinessProfile> {
+        match self {
+            Profile::Individual(_) => None,
+            Profile::Business(profile) => Some(profile),
+        }
+    }
+}
+
+impl EntityTagged for Profile {
+    type Individual = IndividualProfile;
+    type Business = BusinessProfile;
+
+    fn individual(value: IndividualProfile) -> Self {
+        Profile::Individual(value)
+    }
+
+    fn business(value: BusinessProfile) -> Self {
+        Profile::Business(value)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct BeneficialOwnerReference {
+pub struct BeneficialOwnerReference<P = Profile> {
     pub id: Option<String>,
     #[serde(rename = "type")]
     pub reference_type: EntityType,
     pub ownership_percent: Decimal,
-    #[serde(with = "either::serde_untagged")]
-    pub profile: Either<BusinessProfile, IndividualProfile>,
+    pub profile: P,
+}
+
+/// The profile is selected by `type`, failing if it doesn't match. Only if the type is unknown to this crate is the profile
+/// recognized by its shape, trying an individual first, for `validate_business_identity` to report.
+impl<'de> Deserialize<'de> for BeneficialOwnerReference<Profile> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = BeneficialOwnerReference::<Secret<serde_json::Value>>::deserialize(deserializer)?;
+        let profile = match Profile::from_tagged(&wire.reference_type, "profile", &wire.profile) {
+            Err(e) if matches!(wire.reference_type, EntityType::Unknown(_)) => IndividualProfile::deserialize(wire.profile.expose())
+                .map(Profile::Individual)
+                .or_else(|_| BusinessProfile::deserialize(wire.profile.expose()).map(Profile::Business))
+                .map_err(|_| e)?,
+            profile => profile?,
+        };
+        Ok(BeneficialOwnerReference {
+            profile,
+            id: wire.id,
+            reference_type: wire.reference_type,
+            ownership_percent: wire.ownership_percent,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -205,6 +424,9 @@ pub enum IdentityDocumentType {
     DriversLicense,
     StateIdCard,
     Passport,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -214,15 +436,21 @@ pub enum MaritalStatus {
     Married,
     Separated,
     Divorced,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IdentityDocument {
-    pub download_url: String,
+    pub download_url: Secret<String>,
     /// Number of seconds until expiration
     pub download_url_expires: u64,
     #[serde(rename = "type")]
     pub document_type: IdentityDocumentType,
+    /// When the document was fetched from the API, set by `Client`. `download_url_expires` counts from this moment.
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -284,14 +512,16 @@ pub struct IndividualRiskMonitorMatch {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BusinessIdentityDetails {
     pub business_type: BusinessType,
-    #[serde(with = "time::serde::rfc3339")]
+    #[serde(with = "lenient")]
     pub completed_at: DateTime,
     pub control_persons: Vec<ControlPersonReference>,
     pub created_by: IndividualProfile,
     pub direct_beneficial_owners: Vec<BeneficialOwnerReference>,
-    #[serde(with = "time::serde::rfc3339")]
+    #[serde(with = "lenient")]
     pub expires_at: DateTime,
-    pub foreign_tax_id: String,
+    /// As received, see `parse_foreign_tax_id()`
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub foreign_tax_id: Option<Secret<String>>,
     pub identity_files: Vec<IdentityDocument>,
     pub incorporation_country: CountryCode,
     pub incorporation_state: Option<String>,
@@ -299,34 +529,75 @@ pub struct BusinessIdentityDetails {
     pub primary_contact: IndividualProfile,
     pub principal_location: Location,
     pub risk_monitor_matches: Vec<BusinessRiskMonitorMatch>,
-    pub us_tax_id: String, // TODO KYC-321 is this optional?
+    /// As received, see `parse_us_tax_id()`. None if the API sent an empty value.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub us_tax_id: Option<Secret<String>>,
     pub user_session: UserSession,
 }
 
+/// Tax IDs are kept as received, so a value failing validation doesn't fail the whole response
+impl BusinessIdentityDetails {
+    pub fn parse_us_tax_id(&self) -> Result<Option<Secret<Ein>>, InvalidValue> {
+        parse_optional(&self.us_tax_id)
+    }
+
+    pub fn parse_foreign_tax_id(&self) -> Result<Option<Secret<ForeignTaxId>>, InvalidValue> {
+        parse_optional(&self.foreign_tax_id)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct IndividualIdentityDetails {
-    pub birth_date: Date,
+    pub birth_date: Secret<Date>,
     pub citizenship_country: CountryCode,
-    #[serde(with = "time::serde::rfc3339")]
+    #[serde(with = "lenient")]
     pub completed_at: DateTime,
     pub created_by: IndividualProfile,
     pub domicile_location: Location,
-    pub email: String,
-    #[serde(with = "time::serde::rfc3339")]
+    /// As received, see `parse_email()`
+    pub email: Secret<String>,
+    #[serde(with = "lenient")]
     pub expires_at: DateTime,
     pub first_name: String,
     pub last_name: String,
     pub identity_files: Vec<IdentityDocument>,
     pub marital_status: MaritalStatus,
-    /// Phone number is in E.164 format
-    pub phone: String,
+    /// As received, see `parse_phone()`
+    pub phone: Secret<String>,
     pub residence_location: Location,
     pub risk_monitor_matches: Vec<IndividualRiskMonitorMatch>,
-    pub us_tax_id: String, // TODO KYC-321 is this optional?
-    pub foreign_tax_id: Option<String>,
+    /// As received, see `parse_us_tax_id()`. None for subjects without a US tax ID, the API may send an empty value.
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub us_tax_id: Option<Secret<String>>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    pub foreign_tax_id: Option<Secret<String>>,
     pub user_session: UserSession,
 }
 
+/// Contact details and tax IDs are kept as received, so a value failing validation doesn't fail the whole response.
+/// These validate them on each call.
+impl IndividualIdentityDetails {
+    pub fn parse_email(&self) -> Result<Secret<EmailAddress>, InvalidValue> {
+        self.email.expose().parse()
+    }
+
+    pub fn parse_phone(&self) -> Result<Secret<PhoneNumber>, InvalidValue> {
+        self.phone.expose().parse()
+    }
+
+    pub fn parse_us_tax_id(&self) -> Result<Option<Secret<UsTaxId>>, InvalidValue> {
+        parse_optional(&self.us_tax_id)
+    }
+
+    pub fn parse_foreign_tax_id(&self) -> Result<Option<Secret<ForeignTaxId>>, InvalidValue> {
+        parse_optional(&self.foreign_tax_id)
+    }
+}
+
+fn parse_optional<T: Erase + FromStr<Err = InvalidValue>>(value: &Option<Secret<String>>) -> Result<Option<Secret<T>>, InvalidValue> {
+    value.as_ref().map(|value| value.expose().parse()).transpose()
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
 #[serde(rename_all = "kebab-case")]
 pub enum ProvidingFor {
@@ -337,9 +608,12 @@ pub enum ProvidingFor {
     ControlledBusiness,
     /// The user has authenticated on behalf of another person and is sharing that person's information (this option is only possible with the identity scope)
     OtherIndividual,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum RevokeType {
     /// The subject (or an associated individual) requested to revoke access to the subject's data
@@ -348,62 +622,239 @@ pub enum RevokeType {
     Partner,
     /// Access to this subject's data was revoked by our internal systems
     System,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct ProfileResponse {
+pub struct ProfileResponse<P = Profile> {
     pub id: EntityId,
     #[serde(rename = "type")]
     pub entity_type: EntityType,
-    #[serde(with = "either::serde_untagged")]
-    pub profile: Either<IndividualProfile, BusinessProfile>,
+    pub profile: P,
     pub user_id: String,
     pub user_profile: IndividualProfile,
     pub user_providing_for: ProvidingFor,
-    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(with = "lenient::option")]
     pub access_expires_at: Option<DateTime>,
     pub access_revoked_by: Option<RevokeType>,
+    /// When the response was fetched from the API, set by `Client`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
+}
+
+impl<'de> Deserialize<'de> for ProfileResponse<Profile> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = ProfileResponse::<Secret<serde_json::Value>>::deserialize(deserializer)?;
+        Ok(ProfileResponse {
+            profile: Profile::from_tagged(&wire.entity_type, "profile", &wire.profile)?,
+            id: wire.id,
+            entity_type: wire.entity_type,
+            user_id: wire.user_id,
+            user_profile: wire.user_profile,
+            user_providing_for: wire.user_providing_for,
+            access_expires_at: wire.access_expires_at,
+            access_revoked_by: wire.access_revoked_by,
+            fetched_at: wire.fetched_at,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Accreditations {
+    Individual(Vec<IndividualAccreditation>),
+    Business(Vec<BusinessAccreditation>),
+}
+
+impl Accreditations {
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            Accreditations::Individual(_) => EntityType::Individual,
+            Accreditations::Business(_) => EntityType::Business,
+        }
+    }
+
+    pub fn as_individual(&self) -> Option<&[IndividualAccreditation]> {
+        match self {
+            Accreditations::Individual(accreditations) => Some(accreditations),
+            Accreditations::Business(_) => None,
+        }
+    }
+
+    pub fn as_business(&self) -> Option<&[BusinessAccreditation]> {
+        match self {
+            Accreditations::Individual(_) => None,
+            Accreditations::Business(accreditations) => Some(accreditations),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Accreditations::Individual(accreditations) => accreditations.len(),
+            Accreditations::Business(accreditations) => accreditations.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl EntityTagged for Accreditations {
+    type Individual = Vec<IndividualAccreditation>;
+    type Business = Vec<BusinessAccreditation>;
+
+    fn individual(value: Vec<IndividualAccreditation>) -> Self {
+        Accreditations::Individual(value)
+    }
+
+    fn business(value: Vec<BusinessAccreditation>) -> Self {
+        Accreditations::Business(value)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct AccreditationsResponse {
+pub struct AccreditationsResponse<P = Accreditations> {
     pub id: EntityId,
     #[serde(rename = "type")]
     pub entity_type: EntityType,
     pub user_id: String,
-    #[serde(with = "time::serde::timestamp::option")]
+    #[serde(with = "lenient::option")]
     pub indicated_unaccredited: Option<DateTime>,
-    #[serde(with = "either::serde_untagged")]
-    pub accreditations: Either<Vec<IndividualAccreditation>, Vec<BusinessAccreditation>>,
+    pub accreditations: P,
     // pub user_providing_for: ProvidingFor, // Missing from docs
+    /// When the response was fetched from the API, set by `Client`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
+}
+
+impl<'de> Deserialize<'de> for AccreditationsResponse<Accreditations> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = AccreditationsResponse::<Secret<serde_json::Value>>::deserialize(deserializer)?;
+        Ok(AccreditationsResponse {
+            accreditations: Accreditations::from_tagged(&wire.entity_type, "accreditations", &wire.accreditations)?,
+            id: wire.id,
+            entity_type: wire.entity_type,
+            user_id: wire.user_id,
+            indicated_unaccredited: wire.indicated_unaccredited,
+            fetched_at: wire.fetched_at,
+        })
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum IdentityDetails {
+    Individual(IndividualIdentityDetails),
+    Business(BusinessIdentityDetails),
+}
+
+impl IdentityDetails {
+    pub fn entity_type(&self) -> EntityType {
+        match self {
+            IdentityDetails::Individual(_) => EntityType::Individual,
+            IdentityDetails::Business(_) => EntityType::Business,
+        }
+    }
+
+    pub fn as_individual(&self) -> Option<&IndividualIdentityDetails> {
+        match self {
+            IdentityDetails::Individual(details) => Some(details),
+            IdentityDetails::Business(_) => None,
+        }
+    }
+
+    pub fn as_business(&self) -> Option<&BusinessIdentityDetails> {
+        match self {
+            IdentityDetails::Individual(_) => None,
+            IdentityDetails::Business(details) => Some(details),
+        }
+    }
+
+    pub fn identity_files(&self) -> &[IdentityDocument] {
+        match self {
+            IdentityDetails::Individual(details) => &details.identity_files,
+            IdentityDetails::Business(details) => &details.identity_files,
+        }
+    }
+}
+
+impl EntityTagged for IdentityDetails {
+    type Individual = IndividualIdentityDetails;
+    type Business = BusinessIdentityDetails;
+
+    fn individual(value: IndividualIdentityDetails) -> Self {
+        IdentityDetails::Individual(value)
+    }
+
+    fn business(value: BusinessIdentityDetails) -> Self {
+        IdentityDetails::Business(value)
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct IdentityResponse {
+pub struct IdentityResponse<P = IdentityDetails> {
     pub id: EntityId,
     #[serde(rename = "type")]
     pub entity_type: EntityType,
-    #[serde(with = "either::serde_untagged")]
-    pub identity_details: Either<BusinessIdentityDetails, IndividualIdentityDetails>,
+    pub identity_details: P,
     pub user_id: String,
     pub user_providing_for: ProvidingFor,
-    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(with = "lenient::option")]
     pub access_expires_at: Option<DateTime>,
     pub access_revoked_by: Option<RevokeType>,
+    /// When the response was fetched from the API, set by `Client`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
+}
+
+impl<'de> Deserialize<'de> for IdentityResponse<IdentityDetails> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = IdentityResponse::<Secret<serde_json::Value>>::deserialize(deserializer)?;
+        Ok(IdentityResponse {
+            identity_details: IdentityDetails::from_tagged(&wire.entity_type, "identity_details", &wire.identity_details)?,
+            id: wire.id,
+            entity_type: wire.entity_type,
+            user_id: wire.user_id,
+            user_providing_for: wire.user_providing_for,
+            access_expires_at: wire.access_expires_at,
+            access_revoked_by: wire.access_revoked_by,
+            fetched_at: wire.fetched_at,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct DependencyIdentityResponse {
+pub struct DependencyIdentityResponse<P = IdentityDetails> {
     pub id: EntityId,
     #[serde(rename = "type")]
     pub entity_type: EntityType,
-    #[serde(with = "either::serde_untagged")]
-    pub identity_details: Either<BusinessIdentityDetails, IndividualIdentityDetails>,
+    pub identity_details: P,
     pub user_id: String,
-    #[serde(with = "time::serde::rfc3339::option")]
+    #[serde(with = "lenient::option")]
     pub access_expires_at: Option<DateTime>,
     /// Note: Only possible value is Subject
     pub access_revoked_by: Option<RevokeType>,
+    /// When the response was fetched from the API, set by `Client`
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "lenient::option")]
+    pub fetched_at: Option<DateTime>,
+}
+
+impl<'de> Deserialize<'de> for DependencyIdentityResponse<IdentityDetails> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = DependencyIdentityResponse::<Secret<serde_json::Value>>::deserialize(deserializer)?;
+        Ok(DependencyIdentityResponse {
+            identity_details: IdentityDetails::from_tagged(&wire.entity_type, "identity_details", &wire.identity_details)?,
+            id: wire.id,
+            entity_type: wire.entity_type,
+            user_id: wire.user_id,
+            access_expires_at: wire.access_expires_at,
+            access_revoked_by: wire.access_revoked_by,
+            fetched_at: wire.fetched_at,
+        })
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -429,6 +880,9 @@ pub enum EventType {
     LawEnforcementRiskMonitorMatch,
     /// The entity is possibly considered a Politically Exposed Person
     PepRiskMonitorMatch,
+    /// A value not known to this version of the crate, preserved as received
+    #[serde(untagged)]
+    Unknown(String),
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -445,3 +899,61 @@ pub struct WebhookData {
     pub scope: Scope,
     pub connecting_business_id: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    #[test]
+    fn payload_is_selected_by_type() {
+        let response: DependencyIdentityResponse =
+            serde_json::from_value(dependency_identity("holding", "business", business_identity("Holding LLC", vec![], vec![]))).unwrap();
+
+        assert_eq!(response.identity_details.entity_type(), EntityType::Business);
+        assert_eq!(response.identity_details.as_business().unwrap().name, "Holding LLC");
+        assert!(response.identity_details.as_individual().is_none());
+    }
+
+    #[test]
+    fn error_names_the_failing_field() {
+        let mut details = business_identity("Holding LLC", vec![], vec![]);
+        details.as_object_mut().unwrap().remove("name");
+
+        let error = serde_json::from_value::<DependencyIdentityResponse>(dependency_identity("holding", "business", details)).unwrap_err();
+
+        assert_eq!(error.to_string(), "invalid business `identity_details`: missing field `name`");
+    }
+
+    #[test]
+    fn invalid_values_are_reported_on_access() {
+        let details = with(individual_identity("Alice", "Smith"), serde_json::json!({ "email": "alice at example.com", "us_tax_id": "000-00-0000" }));
+        let response = with(dependency_identity("alice", "individual", details), serde_json::json!({ "user_providing_for": "self" }));
+
+        let response: IdentityResponse = serde_json::from_value(response).unwrap();
+        let details = response.identity_details.as_individual().unwrap();
+
+        assert_eq!(details.email.expose(), "alice at example.com");
+        assert_eq!(details.parse_email().err(), Some(InvalidValue("email address")));
+        assert_eq!(details.parse_us_tax_id().err(), Some(InvalidValue("US tax ID")));
+        assert_eq!(details.parse_phone().unwrap().expose().to_string(), "+12025550123");
+        assert_eq!(details.parse_foreign_tax_id(), Ok(None));
+        assert_eq!(details.created_by.parse_email().unwrap().unwrap().expose().domain(), "example.com");
+    }
+
+    #[test]
+    fn unknown_enum_values_are_preserved() {
+        let webhook: WebhookData = serde_json::from_value(serde_json::json!({
+            "entity": { "id": "subject", "type": "individual" },
+            "event": "new_fancy_event",
+            "scope": "identity",
+            "connecting_business_id": null,
+        }))
+        .unwrap();
+
+        assert_eq!(webhook.event, EventType::Unknown("new_fancy_event".to_string()));
+        assert_eq!(webhook.scope, Scope::Identity);
+        assert_eq!(serde_json::to_value(&webhook.event).unwrap(), "new_fancy_event");
+        assert_eq!(BusinessType::Unknown("Cooperative".to_string()).to_string(), "Cooperative");
+    }
+}
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::types::*;
+use json_api_client::types::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Suspicious, but possibly legitimate (e.g. two owners with the same name)
+    Warning,
+    /// The data is inconsistent and should not be relied on
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let str = match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FindingKind {
+    /// Ownership percent is not in the (0, 100] range
+    OwnershipPercentOutOfRange { ownership_percent: Decimal },
+    /// Sum of the direct beneficial owners' ownership percents is above 100
+    TotalOwnershipExceeded { total_percent: Decimal },
+    /// The same owner ID appears more than once
+    DuplicateOwnerId { id: String, first_index: usize },
+    /// The same owner name appears more than once
+    DuplicateOwnerName { name: String, first_index: usize },
+    /// Owner `type` is not known to this crate, its profile was recognized by its shape
+    UnknownOwnerType { reference_type: EntityType },
+    /// Control persons are documented to always be individuals
+    ControlPersonNotIndividual { reference_type: EntityType },
+}
+
+impl fmt::Display for FindingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FindingKind::OwnershipPercentOutOfRange { ownership_percent } => write!(f, "ownership percent {} is out of range", ownership_percent),
+            FindingKind::TotalOwnershipExceeded { total_percent } => write!(f, "total ownership {}% exceeds 100%", total_percent),
+            FindingKind::DuplicateOwnerId { id, first_index } => write!(f, "owner ID '{}' already listed at index {}", id, first_index),
+            FindingKind::DuplicateOwnerName { name, first_index } => write!(f, "owner name '{}' already listed at index {}", name, first_index),
+            FindingKind::UnknownOwnerType { reference_type } => write!(f, "owner type '{}' is unknown", reference_type),
+            FindingKind::ControlPersonNotIndividual { reference_type } => write!(f, "control person has type '{}' instead of individual", reference_type),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationFinding {
+    pub severity: Severity,
+    /// Path of the offending field, e.g. `direct_beneficial_owners[1].ownership_percent`
+    pub field: String,
+    pub kind: FindingKind,
+}
+
+impl fmt::Display for ValidationFinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}: {}", self.severity, self.field, self.kind)
+    }
+}
+
+/// Checks the beneficial owners and control persons of a business for internal consistency.
+/// Returns an empty list if no problems were found.
+pub fn validate_business_identity(details: &BusinessIdentityDetails) -> Vec<ValidationFinding> {
+    let mut findings = Vec::new();
+    let hundred = Decimal::from(100);
+
+    let mut total_percent = Decimal::ZERO;
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+    let mut names: HashMap<String, usize> = HashMap::new();
+
+    for (i, owner) in details.direct_beneficial_owners.iter().enumerate() {
+        let field = format!("direct_beneficial_owners[{}]", i);
+
+        if owner.ownership_percent <= Decimal::ZERO || owner.ownership_percent > hundred {
+            findings.push(ValidationFinding {
+                severity: Severity::Error,
+                field: format!("{}.ownership_percent", field),
+                kind: FindingKind::OwnershipPercentOutOfRange {
+                    ownership_percent: owner.ownership_percent,
+                },
+            });
+        }
+        total_percent += owner.ownership_percent;
+
+        if let Some(id) = &owner.id {
+            if let Some(first_index) = ids.get(id.as_str()) {
+                findings.push(ValidationFinding {
+                    severity: Severity::Error,
+                    field: format!("{}.id", field),
+                    kind: FindingKind::DuplicateOwnerId {
+                        id: id.clone(),
+                        first_index: *first_index,
+                    },
+                });
+            } else {
+                ids.insert(id, i);
+            }
+        }
+
+        let name = match &owner.profile {
+            Profile::Business(business) => business.name.clone(),
+            Profile::Individual(individual) => format!("{} {}", individual.first_name, individual.last_name),
+        };
+        let normalized_name = name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        if let Some(first_index) = names.get(&normalized_name) {
+            findings.push(ValidationFinding {
+                severity: Severity::Warning,
+                field: format!("{}.profile", field),
+                kind: FindingKind::DuplicateOwnerName {
+                    name,
+                    first_index: *first_index,
+                },
+            });
+        } else {
+            names.insert(normalized_name, i);
+        }
+
+        if let EntityType::Unknown(_) = owner.reference_type {
+            findings.push(ValidationFinding {
+                severity: Severity::Warning,
+                field: format!("{}.type", field),
+                kind: FindingKind::UnknownOwnerType {
+                    reference_type: owner.reference_type.clone(),
+                },
+            });
+        }
+    }
+
+    if total_percent > hundred {
+        findings.push(ValidationFinding {
+            severity: Severity::Error,
+            field: "direct_beneficial_owners".to_string(),
+            kind: FindingKind::TotalOwnershipExceeded { total_percent },
+        });
+    }
+
+    for (i, person) in details.control_persons.iter().enumerate() {
+        if person.reference_type != EntityType::Individual {
+            findings.push(ValidationFinding {
+                severity: Severity::Error,
+                field: format!("control_persons[{}].type", i),
+                kind: FindingKind::ControlPersonNotIndividual {
+                    reference_type: person.reference_type.clone(),
+                },
+            });
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+    use serde_json::json;
+
+    #[test]
+    fn consistent_identity_has_no_findings() {
+        let details: BusinessIdentityDetails = serde_json::from_value(business_identity(
+            "Root Corp",
+            vec![individual_owner("alice", "Alice", "Smith", 60), business_owner("holding", "Holding LLC", 40)],
+            vec![control_person("carol", "Carol", "White", "CEO")],
+        ))
+        .unwrap();
+
+        assert!(validate_business_identity(&details).is_empty());
+    }
+
+    fn findings(owners: Vec<serde_json::Value>, control_persons: Vec<serde_json::Value>) -> Vec<ValidationFinding> {
+        let details: BusinessIdentityDetails = serde_json::from_value(business_identity("Root Corp", owners, control_persons)).unwrap();
+        validate_business_identity(&details)
+    }
+
+    #[test]
+    fn duplicate_owner_ids_are_errors() {
+        let findings = findings(vec![individual_owner("alice", "Alice", "Smith", 50), individual_owner("alice", "Bob", "Jones", 50)], vec![]);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[0].to_string(), "error at direct_beneficial_owners[1].id: owner ID 'alice' already listed at index 0");
+    }
+
+    #[test]
+    fn duplicate_owner_names_are_warnings() {
+        let findings = findings(
+            vec![
+                individual_owner("alice", "Alice", "Smith", 50),
+                individual_owner("alice-2", "alice ", "SMITH", 50),
+            ],
+            vec![],
+        );
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert_eq!(findings[0].field, "direct_beneficial_owners[1].profile");
+    }
+
+    #[test]
+    fn ownership_percents_above_100_are_out_of_range() {
+        let findings = findings(vec![individual_owner("bob", "Bob", "Jones", 120)], vec![]);
+        let fields: Vec<&str> = findings.iter().map(|f| f.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["direct_beneficial_owners[0].ownership_percent", "direct_beneficial_owners"]);
+    }
+
+    #[test]
+    fn total_ownership_above_100_is_an_error() {
+        let findings = findings(vec![individual_owner("alice", "Alice", "Smith", 60), individual_owner("bob", "Bob", "Jones", 50)], vec![]);
+
+        assert_eq!(
+            findings[0].kind,
+            FindingKind::TotalOwnershipExceeded {
+                total_percent: Decimal::from(110)
+            }
+        );
+    }
+
+    #[test]
+    fn control_persons_must_be_individuals() {
+        let business_person = with(control_person("carol", "Carol", "White", "CEO"), json!({ "type": "business" }));
+        let findings = findings(vec![], vec![business_person]);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].to_string(), "error at control_persons[0].type: control person has type 'business' instead of individual");
+    }
+
+    #[test]
+    fn profile_not_matching_owner_type_fails_to_parse() {
+        let owner = with(individual_owner("alice", "Alice", "Smith", 60), json!({ "type": "business" }));
+        let error = serde_json::from_value::<BusinessIdentityDetails>(business_identity("Root Corp", vec![owner], vec![])).unwrap_err();
+
+        assert!(error.to_string().starts_with("invalid business `profile`: missing field `name`"), "{}", error);
+    }
+
+    #[test]
+    fn reports_owner_of_unknown_type() {
+        let owner = with(individual_owner("alice", "Alice", "Smith", 60), json!({ "type": "trust" }));
+        let details: BusinessIdentityDetails = serde_json::from_value(business_identity("Root Corp", vec![owner], vec![])).unwrap();
+
+        let findings = validate_business_identity(&details);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].field, "direct_beneficial_owners[0].type");
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(matches!(findings[0].kind, FindingKind::UnknownOwnerType { .. }));
+        assert_eq!(details.direct_beneficial_owners[0].profile.as_individual().unwrap().first_name, "Alice");
+    }
+}
@@ -0,0 +1,343 @@
+use serde::de::{Deserialize, Deserializer};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+use zeroize::Zeroize;
+
+use crate::secret::Erase;
+
+/// A value that failed validation. The value itself is left out, as it is usually personal data.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("invalid {0}")]
+pub struct InvalidValue(pub &'static str);
+
+/// Phone number in E.164 format, e.g. `+12025550123`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhoneNumber(String);
+
+impl PhoneNumber {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// All but the last 4 digits hidden, e.g. `+*******0123`
+    pub fn masked(&self) -> String {
+        format!("+{}", mask(&self.0[1..], 4))
+    }
+}
+
+impl FromStr for PhoneNumber {
+    type Err = InvalidValue;
+
+    /// Spaces, dashes, dots and parentheses between the digits are removed
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let digits: String = value
+            .strip_prefix('+')
+            .ok_or(InvalidValue("phone number"))?
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+            .collect();
+        if !(8..=15).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) || digits.starts_with('0') {
+            return Err(InvalidValue("phone number"));
+        }
+        Ok(PhoneNumber(format!("+{}", digits)))
+    }
+}
+
+/// Email address, with the domain in lowercase
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EmailAddress(String);
+
+impl EmailAddress {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn local_part(&self) -> &str {
+        self.0.rsplit_once('@').map(|(local, _)| local).unwrap_or_default()
+    }
+
+    pub fn domain(&self) -> &str {
+        self.0.rsplit_once('@').map(|(_, domain)| domain).unwrap_or_default()
+    }
+
+    /// Only the first character of the local part shown, e.g. `a***@example.com`
+    pub fn masked(&self) -> String {
+        let first = self.local_part().chars().next().unwrap_or_default();
+        format!("{}***@{}", first, self.domain())
+    }
+}
+
+impl FromStr for EmailAddress {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = InvalidValue("email address");
+        let (local, domain) = value.trim().rsplit_once('@').ok_or(invalid)?;
+        let valid_local = !local.is_empty() && local.len() <= 64 && local.chars().all(|c| c.is_ascii_graphic() && c != '@');
+        let labels: Vec<&str> = domain.split('.').collect();
+        let valid_domain = labels.len() >= 2
+            && labels.iter().all(|label| {
+                !label.is_empty() && !label.starts_with('-') && !label.ends_with('-') && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if !valid_local || !valid_domain {
+            return Err(invalid);
+        }
+        Ok(EmailAddress(format!("{}@{}", local, domain.to_ascii_lowercase())))
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UsTaxIdKind {
+    /// Social Security Number
+    Ssn,
+    /// Individual Taxpayer Identification Number
+    Itin,
+}
+
+/// Taxpayer ID of an individual (SSN or ITIN), formatted as `XXX-XX-XXXX`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UsTaxId {
+    kind: UsTaxIdKind,
+    digits: String,
+}
+
+impl UsTaxId {
+    pub fn kind(&self) -> UsTaxIdKind {
+        self.kind
+    }
+
+    /// The 9 digits without separators
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    /// Only the last 4 digits shown, e.g. `***-**-6789`
+    pub fn masked(&self) -> String {
+        format!("***-**-{}", &self.digits[5..])
+    }
+}
+
+impl FromStr for UsTaxId {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = InvalidValue("US tax ID");
+        let digits = nine_digits(value).ok_or(invalid)?;
+        let area: u32 = digits[0..3].parse().map_err(|_| invalid)?;
+        let group: u32 = digits[3..5].parse().map_err(|_| invalid)?;
+        let serial = &digits[5..];
+
+        let kind = if area >= 900 {
+            // ITIN group numbers are 50-65, 70-88, 90-92 or 94-99
+            if !matches!(group, 50..=65 | 70..=88 | 90..=92 | 94..=99) {
+                return Err(invalid);
+            }
+            UsTaxIdKind::Itin
+        } else {
+            if area == 0 || area == 666 || group == 0 || serial == "0000" {
+                return Err(invalid);
+            }
+            UsTaxIdKind::Ssn
+        };
+        Ok(UsTaxId { kind, digits })
+    }
+}
+
+impl fmt::Display for UsTaxId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}-{}", &self.digits[0..3], &self.digits[3..5], &self.digits[5..])
+    }
+}
+
+/// Employer Identification Number of a business, formatted as `XX-XXXXXXX`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ein(String);
+
+impl Ein {
+    /// The 9 digits without separators
+    pub fn digits(&self) -> &str {
+        &self.0
+    }
+
+    /// Only the last 4 digits shown, e.g. `**-***6789`
+    pub fn masked(&self) -> String {
+        format!("**-***{}", &self.0[5..])
+    }
+}
+
+impl FromStr for Ein {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match nine_digits(value) {
+            Some(digits) if !digits.starts_with("00") => Ok(Ein(digits)),
+            _ => Err(InvalidValue("EIN")),
+        }
+    }
+}
+
+impl fmt::Display for Ein {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", &self.0[0..2], &self.0[2..])
+    }
+}
+
+/// Tax ID issued outside the US. Formats vary by country, so only the character set and length are checked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ForeignTaxId(String);
+
+impl ForeignTaxId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Only the last 4 characters shown
+    pub fn masked(&self) -> String {
+        mask(&self.0, 4)
+    }
+}
+
+impl FromStr for ForeignTaxId {
+    type Err = InvalidValue;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let valid_chars = value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '.' | '/'));
+        if value.is_empty() || value.len() > 64 || !valid_chars {
+            return Err(InvalidValue("foreign tax ID"));
+        }
+        Ok(ForeignTaxId(value.to_string()))
+    }
+}
+
+/// 9 digits, optionally separated by dashes or spaces
+fn nine_digits(value: &str) -> Option<String> {
+    let digits: String = value.trim().chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+    (digits.len() == 9 && digits.chars().all(|c| c.is_ascii_digit())).then_some(digits)
+}
+
+fn mask(value: &str, visible: usize) -> String {
+    let hidden = value.chars().count().saturating_sub(visible);
+    value.chars().enumerate().map(|(i, c)| if i < hidden { '*' } else { c }).collect()
+}
+
+macro_rules! string_value {
+    ($($value:ty),*) => {$(
+        impl fmt::Display for $value {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.0)
+            }
+        }
+    )*};
+}
+
+string_value!(PhoneNumber, EmailAddress, ForeignTaxId);
+
+macro_rules! erase_string {
+    ($($value:ty => $field:tt),*) => {$(
+        impl Erase for $value {
+            fn erase(&mut self) {
+                self.$field.zeroize();
+            }
+        }
+    )*};
+}
+
+erase_string!(PhoneNumber => 0, EmailAddress => 0, UsTaxId => digits, Ein => 0, ForeignTaxId => 0);
+
+macro_rules! serde_via_str {
+    ($($value:ty),*) => {$(
+        impl Serialize for $value {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $value {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                value.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    )*};
+}
+
+serde_via_str!(PhoneNumber, EmailAddress, UsTaxId, Ein, ForeignTaxId);
+
+/// Deserializes a missing, null or empty string value as None
+pub(crate) fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) if !value.trim().is_empty() => value.parse().map(Some).map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn phone_numbers_are_normalized_to_e164() {
+        let phone: PhoneNumber = "+1 (202) 555-0123".parse().unwrap();
+
+        assert_eq!(phone.to_string(), "+12025550123");
+        assert_eq!(phone.masked(), "+*******0123");
+        assert!("2025550123".parse::<PhoneNumber>().is_err());
+    }
+
+    #[test]
+    fn email_domains_are_lowercased() {
+        let email: EmailAddress = "Alice.Smith@Example.COM".parse().unwrap();
+
+        assert_eq!(email.to_string(), "Alice.Smith@example.com");
+        assert_eq!(email.masked(), "A***@example.com");
+        assert!("alice@localhost".parse::<EmailAddress>().is_err());
+    }
+
+    #[test]
+    fn us_tax_ids_are_classified() {
+        let ssn: UsTaxId = "123456789".parse().unwrap();
+
+        assert_eq!((ssn.kind(), ssn.to_string(), ssn.masked()), (UsTaxIdKind::Ssn, "123-45-6789".to_string(), "***-**-6789".to_string()));
+        assert_eq!("912-70-1234".parse::<UsTaxId>().unwrap().kind(), UsTaxIdKind::Itin);
+    }
+
+    #[test]
+    fn invalid_us_tax_ids_are_rejected() {
+        assert_eq!("666-12-3456".parse::<UsTaxId>(), Err(InvalidValue("US tax ID")));
+        assert!("912-40-1234".parse::<UsTaxId>().is_err());
+    }
+
+    #[test]
+    fn eins_and_foreign_tax_ids_are_masked() {
+        let ein: Ein = "12 3456789".parse().unwrap();
+
+        assert_eq!((ein.to_string(), ein.masked()), ("12-3456789".to_string(), "**-***6789".to_string()));
+        assert_eq!("DE 123/456/78901".parse::<ForeignTaxId>().unwrap().masked(), "************8901");
+    }
+
+    #[test]
+    fn empty_strings_are_none() {
+        #[derive(serde::Deserialize)]
+        struct Details {
+            #[serde(default, deserialize_with = "empty_as_none")]
+            us_tax_id: Option<UsTaxId>,
+            #[serde(default, deserialize_with = "empty_as_none")]
+            foreign_tax_id: Option<ForeignTaxId>,
+        }
+
+        let details: Details = serde_json::from_value(serde_json::json!({ "us_tax_id": "" })).unwrap();
+        assert_eq!((details.us_tax_id, details.foreign_tax_id), (None, None));
+        let error = serde_json::from_value::<Details>(serde_json::json!({ "us_tax_id": "000-00-0000" })).err().unwrap();
+        assert!(!error.to_string().contains("000"), "{}", error);
+    }
+}
@@ -20,9 +20,8 @@ fn get_client() -> Client {
 async fn exchange_code() {
     let client = get_client();
     let code = AuthorizationCode::from("TEST".to_string());
-    let resp = client.exchange_code(code).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let token = client.exchange_code(code).await.unwrap();
+    assert_eq!(format!("{:?}", token), "[REDACTED]");
 }
 
 #[test(tokio::test)]
@@ -30,36 +29,35 @@ async fn exchange_code() {
 async fn refresh_token() {
     let client = get_client();
     let token = RefreshToken::from(REFRESH_TOKEN);
-    let resp = client.refresh_token(&token).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let token = client.refresh_token(&token).await.unwrap();
+    assert_eq!(format!("{:?}", token), "[REDACTED]");
 }
 
 #[test(tokio::test)]
 #[ignore]
 async fn get_profile() {
     let client = get_client();
-    let resp = client.get_profile(ACCESS_TOKEN).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let resp = client.get_profile(ACCESS_TOKEN).await.unwrap();
+    assert_eq!(resp.profile.entity_type(), resp.entity_type);
+    assert!(resp.fetched_at.is_some());
 }
 
 #[test(tokio::test)]
 #[ignore]
 async fn get_accreditations() {
     let client = get_client();
-    let resp = client.get_accreditations(ACCESS_TOKEN).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let resp = client.get_accreditations(ACCESS_TOKEN).await.unwrap();
+    assert_eq!(resp.accreditations.entity_type(), resp.entity_type);
+    assert!(resp.fetched_at.is_some());
 }
 
 #[test(tokio::test)]
 #[ignore]
 async fn get_identity() {
     let client = get_client();
-    let resp = client.get_identity(ACCESS_TOKEN).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let resp = client.get_identity(ACCESS_TOKEN).await.unwrap();
+    assert_eq!(resp.identity_details.entity_type(), resp.entity_type);
+    assert!(resp.fetched_at.is_some());
 }
 
 #[test(tokio::test)]
@@ -67,7 +65,19 @@ async fn get_identity() {
 async fn get_dependency_identity() {
     let client = get_client();
     let dependency_id = "VXNlcjoyODIw";
-    let resp = client.get_dependency_identity(dependency_id, ACCESS_TOKEN).await;
-    println!("Resp: {:#?}", resp);
-    assert!(resp.is_ok());
+    let resp = client.get_dependency_identity(dependency_id, ACCESS_TOKEN).await.unwrap();
+    assert_eq!(resp.id, dependency_id);
+    assert_eq!(resp.identity_details.entity_type(), resp.entity_type);
+}
+
+#[test(tokio::test)]
+#[ignore]
+async fn download_identity_document() {
+    let client = get_client();
+    let identity = client.get_identity(ACCESS_TOKEN).await.unwrap();
+    let document = &identity.identity_details.identity_files()[0];
+    let mut content = Vec::new();
+    let download = client.download_document(document, &mut content).await.unwrap();
+    assert_eq!(download.size, content.len() as u64);
+    assert!(download.size > 0);
 }